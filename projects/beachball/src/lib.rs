@@ -1,401 +1,536 @@
-use base64::prelude::*;
+use common::apng::Apng;
+use common::clock::Clock;
+use common::contrast::contrast_scheme;
+use common::easing::{Easing, PositiveCos};
+use common::frame_options::{FrameFormat, FrameOptions};
+use common::gltf;
+use common::palette::{Scheme, SchemeChoice, SchemeName, UNCOLOURED};
+use common::parameters::{self, AnimationParameters, ParameterSchema};
+use common::phase::Phase;
+use common::render_options::RenderOptions;
+use common::scene_graph::Node;
+use common::sprite_sheet::SpriteSheet;
+use common::timeline::Timeline;
+use common::validation::ContinuityReport;
+use common::{
+    frame_pixels, grayscale_png, png_data_url, render, tag_set, Group, Point3d, Sphere,
+    DEFAULT_FRAMES_PER_SECOND,
+};
+use serde::{Deserialize, Serialize};
 use std::f64::consts::TAU;
-use std::fmt;
-use std::io::BufWriter;
-use std::io::Cursor;
-use std::ops::{Add, Mul, Sub};
 use wasm_bindgen::prelude::*;
-const WIDTH: u32 = 1024;
-const HEIGHT: u32 = 1024;
+const NAME: &str = "beachball";
+const SCENE_DESCRIPTION: &str = include_str!("../scenes/beachball.json");
+#[cfg(feature = "script")]
+const SCRIPT: &str = include_str!("../scripts/beachball.rhai");
+const DURATION_SECONDS: f64 = 12.0;
 
 #[wasm_bindgen]
 pub fn data_url() -> String {
-    let total_frames = 600;
-    let mut fake_file: Cursor<Vec<u8>> = Cursor::new(Vec::new());
-    {
-        // Start a block so that the APNG file will be closed after all of the frames have been written
-        let w = &mut BufWriter::new(&mut fake_file);
-
-        let mut encoder = png::Encoder::new(w, WIDTH, HEIGHT);
-        encoder.set_color(png::ColorType::Grayscale);
-        encoder.set_depth(png::BitDepth::Eight);
-        match encoder.set_animated(total_frames, 0) {
-            Ok(_) => (),
-            Err(error) => eprintln!("encoder.set_animated result: {error}"),
-        };
-        match encoder.set_frame_delay(2, 100) {
-            Ok(_) => (),
-            Err(error) => eprintln!("encoder.set_frame_delay result: {error}"),
-        };
-        let mut writer = encoder.write_header().unwrap();
-
-        for frame_index in 0..total_frames {
-            let animation_fraction = frame_index as f64 / total_frames as f64;
-            let beachball = Group::beachball(animation_fraction)
-                .rotate(TAU / 8.0, &Point3d::x_axis())
-                .expect("The x_axis vector is not zero.")
-                * 0.25;
-            save_apng_frame(&mut writer, WIDTH, HEIGHT, &beachball.spheres);
-        }
-    }
+    let apng = animation(&RenderOptions::default());
+    png_data_url(&apng.encode_with(&apng.optimise()))
+}
 
-    let base64_data = BASE64_STANDARD.encode(fake_file.get_ref());
-    format!("data:image/png;base64,{}", base64_data)
-}
-
-fn save_apng_frame(
-    writer: &mut png::Writer<&mut BufWriter<&mut Cursor<Vec<u8>>>>,
-    width: u32,
-    height: u32,
-    spheres: &[Sphere],
-) {
-    let mut data = vec![];
-    for y in 0..width {
-        for x in 0..height {
-            let normalised_x: f64 = x as f64 / width as f64 * 2.0 - 1.0;
-            let normalised_y: f64 = y as f64 / height as f64 * 2.0 - 1.0;
-            let result = spheres
-                .iter()
-                .filter_map(sphere_with_intersection_distance(
-                    normalised_x,
-                    normalised_y,
-                ))
-                .reduce(|current, next| {
-                    if next.distance < current.distance {
-                        next
-                    } else {
-                        current
-                    }
-                });
-            let grey = match result {
-                Some(intersection) => intersection.sphere.grey_value,
-                _ => 0,
-            };
-            data.push(grey)
-        }
-    }
-    writer.write_image_data(&data).unwrap();
+/// The animation rendered at `frames_per_second`, with each frame's delay matched to the frame rate.
+#[wasm_bindgen]
+pub fn data_url_at_frame_rate(frames_per_second: f64) -> Result<String, String> {
+    let options = RenderOptions {
+        frames_per_second,
+        ..RenderOptions::default()
+    };
+    options.validate(&beachball_phases())?;
+    let apng = animation(&options);
+    Ok(png_data_url(&apng.encode_with(&apng.optimise())))
 }
 
-struct Group {
-    spheres: Vec<Sphere>,
+/// The animation rendered with the `RenderOptions` given as JSON, for per-phase delays, a loop count
+/// or playing once.
+#[wasm_bindgen]
+pub fn data_url_with_options(options_json: &str) -> Result<String, String> {
+    let options = RenderOptions::from_json(options_json, &beachball_phases())?;
+    let apng = animation(&options);
+    Ok(png_data_url(&apng.encode_with(&apng.optimise())))
 }
 
-impl Group {
-    fn new(spheres: Vec<Sphere>) -> Self {
-        Self { spheres }
-    }
+/// Compare the size of the animation written as 8-bit grayscale with its size after optimising the
+/// encoding, as JSON.
+#[wasm_bindgen]
+pub fn encoding_report() -> String {
+    serde_json::to_string_pretty(&animation(&RenderOptions::default()).report())
+        .expect("The report contains no maps.")
+}
 
-    fn beachball(animation_fraction: f64) -> Self {
-        Self::new(
-            (0..8)
-                .map(|sphere_index| Sphere {
-                    centre: Point3d {
-                        x: 4.0 * beachball_orbit_radius(animation_fraction) + 0.001,
-                        y: 0.0,
-                        z: 0.0,
-                    }
-                    .rotate(
-                        (sphere_index as f64 / 8.0 + animation_fraction) * TAU,
-                        &Point3d::y_axis(),
-                    )
-                    .expect("The y_axis vector is not zero."),
-                    radius: 1.0,
-                    grey_value: 64 + 21 * (sphere_index % 8),
-                })
-                .collect(),
-        )
-    }
+fn default_clock() -> Clock {
+    Clock::new(DURATION_SECONDS, DEFAULT_FRAMES_PER_SECOND)
+        .expect("The duration and default frame rate are positive.")
+}
 
-    fn rotate(&self, angle: f64, axis: &Point3d) -> Option<Self> {
-        match axis {
-            x if *x == Point3d::origin() => None,
-            _ => Some(Self {
-                spheres: self
-                    .spheres
-                    .iter()
-                    .filter_map(|sphere| sphere.rotate(angle, axis))
-                    .collect(),
-            }),
-        }
-    }
+fn animation(options: &RenderOptions<Parameters>) -> Apng {
+    let (scheme, finished_scheme) = options.colour_schemes();
+    render(
+        options,
+        DURATION_SECONDS,
+        &beachball_phases(),
+        |animation_fraction| coloured_frame(animation_fraction, &options.parameters, &scheme),
+        &coloured_frame(0.0, &options.parameters, &finished_scheme),
+    )
 }
 
-impl Add for Group {
-    type Output = Self;
+/// Tile every `frame_step`th frame of the animation into one sprite sheet, rendering each frame at
+/// `tile_size` pixels square.
+#[wasm_bindgen]
+pub fn sprite_sheet(frame_step: u32, tile_size: u32) -> Result<SpriteSheet, String> {
+    let frame_step = frame_step.max(1);
+    let clock = default_clock();
+    let frames = (0..clock.frame_count())
+        .step_by(frame_step as usize)
+        .map(|frame_index| {
+            let animation_fraction = clock.animation_fraction(frame_index);
+            frame_pixels(tile_size, tile_size, &frame(animation_fraction).spheres)
+        });
+    SpriteSheet::new(
+        NAME,
+        frames,
+        tile_size,
+        tile_size,
+        (1000.0 * frame_step as f64 / DEFAULT_FRAMES_PER_SECOND).round() as u32,
+    )
+}
 
-    fn add(self, rhs: Self) -> Self::Output {
-        let mut spheres = self.spheres;
-        spheres.extend(rhs.spheres);
-        Self { spheres }
-    }
+/// Export the animation as a binary glTF (`.glb`) file, with one node per sphere and translation and
+/// scale keyframes for every frame.
+#[wasm_bindgen]
+pub fn glb() -> Result<Vec<u8>, String> {
+    let clock = default_clock();
+    let frames: Vec<Group> = (0..clock.frame_count())
+        .map(|frame_index| frame(clock.animation_fraction(frame_index)))
+        .collect();
+    gltf::glb(&frames, 1.0 / DEFAULT_FRAMES_PER_SECOND)
 }
 
-impl Add<Point3d> for Group {
-    type Output = Self;
+/// The description of the animation in the JSON scene format that `scene_animation_data_url` reads.
+#[wasm_bindgen]
+pub fn scene_description() -> String {
+    SCENE_DESCRIPTION.to_string()
+}
 
-    fn add(self, rhs: Point3d) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() + rhs.clone())
-                .collect(),
-        }
-    }
+/// Take apart the assembled group given as JSON, in the form `scene_json` gives, and put it back
+/// together as the `ExplodeOptions` given as JSON describe, rendered in the same way as the built-in
+/// animation at the default frame rate.
+#[wasm_bindgen]
+pub fn explode_data_url(group_json: &str, options_json: &str) -> Result<String, String> {
+    common::explode_data_url(group_json, options_json)
 }
 
-impl Sub<Point3d> for Group {
-    type Output = Self;
+/// Check the movement of every sphere taken apart by `explode_data_url` for jumps in value or slope,
+/// and check that it loops back to the assembled group, as JSON.
+#[wasm_bindgen]
+pub fn explode_continuity_report(group_json: &str, options_json: &str) -> Result<String, String> {
+    common::explode_continuity_report(group_json, options_json)
+}
 
-    fn sub(self, rhs: Point3d) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() - rhs.clone())
-                .collect(),
-        }
-    }
+/// Morph between two groups given as JSON, in the form `scene_json` gives, such as the assembled
+/// beachball and the assembled starball from their own modules. The animation holds the first group,
+/// morphs into the second, holds it and morphs back, rendered in the same way as the built-in
+/// animation at the default frame rate. See `common::morph::Morph`.
+#[wasm_bindgen]
+pub fn morph_data_url(from_json: &str, to_json: &str) -> Result<String, String> {
+    common::morph_data_url(from_json, to_json)
 }
 
-impl Mul<f64> for Group {
-    type Output = Self;
+/// A novelty ball built at random from `seed`, coming apart and back together, rendered in the same
+/// way as the built-in animation at the default frame rate. See `common::random_ball::RandomBall`.
+#[wasm_bindgen]
+pub fn random_ball_data_url(seed: u32) -> String {
+    common::random_ball_data_url(seed)
+}
 
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() * rhs)
-                .collect(),
-        }
-    }
+/// The random ball built from `seed` at `animation_fraction` as JSON, in the same form as
+/// `scene_json`, for keeping as a regression fixture.
+#[wasm_bindgen]
+pub fn random_ball_json(seed: u32, animation_fraction: f64) -> String {
+    common::random_ball_json(seed, animation_fraction)
 }
 
-#[derive(Clone)]
-struct Sphere {
-    centre: Point3d,
-    radius: f64,
-    grey_value: u8,
+/// Load an animation described in the JSON scene format and render it in the same way as the
+/// built-in animation, at the default frame rate.
+#[wasm_bindgen]
+pub fn scene_animation_data_url(json: &str) -> Result<String, String> {
+    common::scene_animation_data_url(json, Parameters::DEFAULT_SCHEME)
 }
 
-impl Sphere {
-    fn rotate(&self, angle: f64, axis: &Point3d) -> Option<Self> {
-        Some(Self {
-            centre: self.centre.rotate(angle, axis)?,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        })
-    }
+/// The animation written as a Rhai script, in the form that `script_animation_data_url` reads.
+#[cfg(feature = "script")]
+#[wasm_bindgen]
+pub fn script() -> String {
+    SCRIPT.to_string()
 }
 
-impl fmt::Display for Sphere {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "[{}, {}, {}],",
-            self.centre, self.radius, self.grey_value
-        )
+/// Run an animation written as a Rhai script and render it in the same way as the built-in animation,
+/// at the default frame rate. See `common::script::Script` for what a script can use.
+#[cfg(feature = "script")]
+#[wasm_bindgen]
+pub fn script_animation_data_url(source: &str) -> Result<String, String> {
+    common::script_animation_data_url(source)
+}
+
+/// Render the named animation at a single `animation_fraction`, without rendering the rest of it, as
+/// the PNG file or raw grey values that `options_json` asks for.
+#[wasm_bindgen]
+pub fn render_frame(
+    name: &str,
+    animation_fraction: f64,
+    options_json: &str,
+) -> Result<Vec<u8>, String> {
+    if name != NAME {
+        return Err(format!("This module renders the {NAME}, not \"{name}\"."));
     }
+    if !animation_fraction.is_finite() {
+        return Err("The animation fraction is a finite number.".to_string());
+    }
+    let options = FrameOptions::<Parameters>::from_json(options_json)?;
+    let pixels = frame_pixels(
+        options.width,
+        options.height,
+        &coloured_frame(
+            animation_fraction,
+            &options.parameters,
+            &options.colour_scheme.scheme(&options.parameters),
+        )
+        .spheres,
+    );
+    Ok(match options.format {
+        FrameFormat::Png => grayscale_png(options.width, options.height, &pixels),
+        FrameFormat::Raw => pixels,
+    })
 }
 
-impl Mul<f64> for Sphere {
-    type Output = Self;
+/// The scene at `animation_fraction` as JSON, which `scene_image_data_url` reads back exactly.
+#[wasm_bindgen]
+pub fn scene_json(animation_fraction: f64) -> String {
+    frame(animation_fraction).to_json()
+}
 
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self {
-            centre: self.centre * rhs,
-            radius: self.radius * rhs,
-            grey_value: self.grey_value,
-        }
-    }
+/// Render a scene exported by `scene_json` as a still PNG.
+#[wasm_bindgen]
+pub fn scene_image_data_url(json: &str) -> Result<String, String> {
+    common::scene_image_data_url(json)
 }
 
-impl Add<Point3d> for Sphere {
-    type Output = Self;
+/// The named phases of the animation in order, with their start and end fractions and captions, as
+/// JSON.
+#[wasm_bindgen]
+pub fn phases_json() -> String {
+    serde_json::to_string(&beachball_phases()).expect("A phase contains no maps.")
+}
 
-    fn add(self, rhs: Point3d) -> Self::Output {
-        Self {
-            centre: self.centre + rhs,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        }
-    }
+/// The standard and explanatory colour schemes as JSON, to start a custom scheme from.
+#[wasm_bindgen]
+pub fn colour_schemes_json() -> String {
+    let scheme = |name| SchemeChoice::Named(name).scheme(&Parameters::default());
+    serde_json::json!({
+        "standard": scheme(SchemeName::Standard),
+        "explanatory": scheme(SchemeName::Explanatory),
+    })
+    .to_string()
 }
 
-impl Sub<Point3d> for Sphere {
-    type Output = Self;
+/// The parameters that `RenderOptions` and `FrameOptions` accept to vary the ball, with their
+/// descriptions, types, defaults and ranges, as JSON.
+#[wasm_bindgen]
+pub fn parameter_schema_json() -> String {
+    parameters::schema_json::<Parameters>()
+}
 
-    fn sub(self, rhs: Point3d) -> Self::Output {
-        Self {
-            centre: self.centre - rhs,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        }
-    }
+/// A colour scheme as JSON that gives neighbouring spheres of the assembled beachball greys that differ as
+/// much as possible, to pass as a custom `colour_scheme`. See `common::contrast::contrast_scheme`.
+#[wasm_bindgen]
+pub fn contrast_scheme_json() -> String {
+    serde_json::to_string(&contrast_scheme(&coloured_frame(
+        0.0,
+        &Parameters::default(),
+        &Scheme::new(&[]),
+    )))
+    .expect("A scheme contains no maps.")
 }
 
-#[derive(Clone, PartialEq)]
-struct Point3d {
-    x: f64,
-    y: f64,
-    z: f64,
+/// The same as `contrast_scheme_json` for an animation described in the JSON scene format, which is
+/// assembled in its first frame.
+#[wasm_bindgen]
+pub fn scene_contrast_scheme_json(json: &str) -> Result<String, String> {
+    common::scene_contrast_scheme_json(json)
 }
 
-impl Point3d {
-    fn normalise(&self) -> Option<Self> {
-        let distance = Self::distance(self, &Self::origin());
-        if distance == 0.0 {
-            return None;
-        }
-        let x = self.x / distance;
-        let y = self.y / distance;
-        let z = self.z / distance;
-        Some(Self { x, y, z })
-    }
+/// Check every motion track for jumps in value or slope, and check that the end of the animation leads
+/// back into its first frame, as JSON.
+#[wasm_bindgen]
+pub fn continuity_report() -> String {
+    serde_json::to_string_pretty(&continuity()).expect("The report contains no maps.")
+}
 
-    fn distance(a: &Self, b: &Self) -> f64 {
-        ((a.x - b.x).powf(2.0) + (a.y - b.y).powf(2.0) + (a.z - b.z).powf(2.0)).powf(0.5)
-    }
+fn continuity() -> ContinuityReport {
+    ContinuityReport::new(
+        BeachballTrack::ALL
+            .iter()
+            .map(|track| (format!("{track:?}"), beachball_timeline(*track))),
+        frame,
+    )
+}
 
-    fn origin() -> Self {
-        Self {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        }
-    }
+/// Sample a path described in JSON at `samples` evenly spaced steps of progress from its start to its
+/// end inclusive, giving the points as JSON, for previewing the path a sphere would follow.
+#[wasm_bindgen]
+pub fn sample_path(json: &str, samples: u32) -> Result<String, String> {
+    common::sample_path(json, samples)
+}
 
-    // Based on https://en.wikipedia.org/wiki/Rotation_matrix#Rotation_matrix_from_axis_and_angle
-    fn rotate(&self, angle: f64, axis: &Self) -> Option<Self> {
-        let u = axis.normalise()?;
-        let s = angle.sin();
-        let c = angle.cos();
-        let d = 1.0 - c;
-        Some(Self {
-            x: self.x * (c + u.x.powf(2.0) * d)
-                + self.y * (u.x * u.y * d - u.z * s)
-                + self.z * (u.x * u.z * d + u.y * s),
-            y: self.x * (u.y * u.x * d + u.z * s)
-                + self.y * (c + u.y.powf(2.0) * d)
-                + self.z * (u.y * u.z * d - u.x * s),
-            z: self.x * (u.z * u.x * d - u.y * s)
-                + self.y * (u.z * u.y * d + u.x * s)
-                + self.z * (c + u.z.powf(2.0) * d),
-        })
-    }
+/// Sample the named easing at `samples` evenly spaced points from 0.0 to 1.0 inclusive, for plotting
+/// its curve.
+#[wasm_bindgen]
+pub fn easing_curve(name: &str, samples: u32) -> Result<Vec<f64>, String> {
+    common::easing_curve(name, samples)
+}
 
-    fn x_axis() -> Self {
-        Self {
-            x: 1.0,
-            y: 0.0,
-            z: 0.0,
-        }
-    }
+fn frame(animation_fraction: f64) -> Group {
+    let parameters = Parameters::default();
+    coloured_frame(
+        animation_fraction,
+        &parameters,
+        &SchemeChoice::default_for::<Parameters>().scheme(&parameters),
+    )
+}
 
-    fn y_axis() -> Self {
-        Self {
-            x: 0.0,
-            y: 1.0,
-            z: 0.0,
-        }
-    }
+fn coloured_frame(animation_fraction: f64, parameters: &Parameters, scheme: &Scheme) -> Group {
+    scheme.colour(
+        Node::from(beachball(animation_fraction, parameters))
+            .rotate(TAU / 8.0, &Point3d::x_axis())
+            .expect("The x_axis vector is not zero.")
+            .scale(0.25)
+            .flatten(),
+    )
 }
 
-impl Add for Point3d {
-    type Output = Self;
+fn beachball(animation_fraction: f64, parameters: &Parameters) -> Group {
+    Group::new(
+        (0..parameters.segments)
+            .map(|sphere_index| Sphere {
+                centre: Point3d {
+                    x: 4.0
+                        * beachball_timeline(BeachballTrack::OrbitRadius)
+                            .sample(animation_fraction)
+                        + 0.001,
+                    y: 0.0,
+                    z: 0.0,
+                }
+                .rotate(
+                    (sphere_index as f64 / parameters.segments as f64 + animation_fraction) * TAU,
+                    &Point3d::y_axis(),
+                )
+                .expect("The y_axis vector is not zero."),
+                radius: 1.0,
+                grey_value: UNCOLOURED,
+                name: Some(format!("segment {sphere_index}")),
+                tags: tag_set(&["segment"]),
+            })
+            .collect(),
+    )
+}
 
-    fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-            z: self.z + rhs.z,
-        }
+#[derive(Clone, Copy, Debug)]
+enum BeachballTrack {
+    OrbitRadius,
+}
+
+// One row per keyframe: the fraction of the way through the animation, followed by the value of each
+// track in the order they are listed in `BeachballTrack`
+const BEACHBALL_KEYFRAMES: [(f64, [f64; 1]); 4] =
+    [(0.0, [0.0]), (0.2, [0.0]), (0.6, [1.0]), (1.0, [0.0])];
+
+impl BeachballTrack {
+    const ALL: [Self; 1] = [Self::OrbitRadius];
+
+    fn easing(self) -> Box<dyn Easing> {
+        Box::new(PositiveCos)
     }
 }
 
-impl Sub for Point3d {
-    type Output = Self;
+fn beachball_timeline(track: BeachballTrack) -> Timeline {
+    Timeline::from_table(&BEACHBALL_KEYFRAMES, track as usize).with_easing(track.easing())
+}
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        self + Self {
-            x: -rhs.x,
-            y: -rhs.y,
-            z: -rhs.z,
-        }
+// The name and caption of each phase, one for each step between rows of `BEACHBALL_KEYFRAMES`
+const BEACHBALL_PHASES: [(&str, &str); 3] = [
+    (
+        "Assembled",
+        "The beachball, made of eight spheres of different greys.",
+    ),
+    (
+        "Open",
+        "The eight spheres move out from the centre, showing each one separately.",
+    ),
+    (
+        "Close",
+        "The spheres move back together to make the beachball again.",
+    ),
+];
+
+fn beachball_phases() -> Vec<Phase> {
+    Phase::between_keyframes(&BEACHBALL_KEYFRAMES, &BEACHBALL_PHASES)
+}
+
+/// The parameters that vary the beachball, read from JSON and described in `PARAMETER_SCHEMA`.
+/// Every field is optional.
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Parameters {
+    segments: u32,
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Self { segments: 8 }
     }
 }
 
-impl Mul<f64> for Point3d {
-    type Output = Self;
+const PARAMETER_SCHEMA: &[ParameterSchema] = &[ParameterSchema {
+    name: "segments",
+    description: "The number of segments around the ball, each one sphere.",
+    min: 1.0,
+    max: 32.0,
+}];
+
+impl AnimationParameters for Parameters {
+    const SCHEMA: &'static [ParameterSchema] = PARAMETER_SCHEMA;
+    // The animation shows the ball in its finished colours unless another scheme is chosen
+    const DEFAULT_SCHEME: SchemeName = SchemeName::Standard;
+
+    // Each segment has its own grey, evenly spaced from dark to light, so that 8 segments go up in
+    // steps of 21
+    fn standard_scheme(&self) -> Scheme {
+        Scheme {
+            rules: (0..self.segments)
+                .map(|segment| {
+                    (
+                        format!("segment {segment}"),
+                        (64 + 168 * segment / self.segments) as u8,
+                    )
+                })
+                .collect(),
+        }
+    }
 
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self {
-            x: self.x * rhs,
-            y: self.y * rhs,
-            z: self.z * rhs,
+    // Neighbouring segments alternate between dark and light greys, so that where one segment ends
+    // and the next begins stands out, and each segment still has a grey of its own
+    fn explanatory_scheme(&self) -> Scheme {
+        let pairs = self.segments.div_ceil(2);
+        Scheme {
+            rules: (0..self.segments)
+                .map(|segment| {
+                    (
+                        format!("segment {segment}"),
+                        (48 + 128 * (segment % 2) + 64 * (segment / 2) / pairs) as u8,
+                    )
+                })
+                .collect(),
         }
     }
 }
 
-impl fmt::Display for Point3d {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}, {}, {}", self.x, self.y, self.z)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::animation_checks::AnimationChecks;
+    use common::easing::positive_cos;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn every_track_is_continuous() {
+        for track in continuity().tracks {
+            assert_eq!(
+                track.discontinuities,
+                vec![],
+                "{} is continuous.",
+                track.track
+            );
+        }
     }
-}
 
-struct Point2d {
-    x: f64,
-    y: f64,
-}
+    #[test]
+    fn end_of_the_animation_leads_into_the_first_frame() {
+        assert_eq!(continuity().unmatched_at_loop, Vec::<String>::new());
+    }
 
-impl Point2d {
-    fn distance(a: Self, b: Self) -> f64 {
-        ((a.x - b.x).powf(2.0) + (a.y - b.y).powf(2.0)).powf(0.5)
+    // The piecewise function the keyframes replaced, giving the orbit radius at fraction `a` of the
+    // animation
+    fn beachball_orbit_radius(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => beachball_orbit_radius(a % 1.0 + 1.0),
+            x if x < 0.2 => 0.0,
+            x if x < 1.0 => 1.0 - positive_cos((a - 0.2) * 5.0 / 4.0 * TAU),
+            _ => beachball_orbit_radius(a % 1.0),
+        }
     }
-}
 
-struct Intersection {
-    sphere: Sphere,
-    distance: f64,
-}
+    #[test]
+    fn the_keyframes_match_the_original_piecewise_functions() {
+        for step in -22..=242 {
+            let a = step as f64 / 220.0;
+            let sampled = beachball_timeline(BeachballTrack::OrbitRadius).sample(a);
+            assert!(
+                (sampled - beachball_orbit_radius(a)).abs() < 1e-9,
+                "The orbit radius at {a} is {sampled} rather than {}.",
+                beachball_orbit_radius(a)
+            );
+        }
+    }
 
-fn sphere_with_intersection_distance(
-    x: f64,
-    y: f64,
-) -> impl FnMut(&Sphere) -> Option<Intersection> {
-    move |sphere| {
-        let distance = Point2d::distance(
-            Point2d { x, y },
-            Point2d {
-                x: sphere.centre.x as f64,
-                y: sphere.centre.y as f64,
-            },
+    #[test]
+    fn segments_are_spread_around_the_ball_with_their_own_greys() {
+        let options = FrameOptions::<Parameters>::from_json(r#"{"parameters": {"segments": 6}}"#)
+            .expect("The options are valid.");
+        let group = coloured_frame(
+            0.5,
+            &options.parameters,
+            &options.colour_scheme.scheme(&options.parameters),
+        );
+        let greys: BTreeSet<u8> = group
+            .spheres
+            .iter()
+            .map(|sphere| sphere.grey_value)
+            .collect();
+        assert_eq!(greys, BTreeSet::from([64, 92, 120, 148, 176, 204]));
+        assert!(
+            FrameOptions::<Parameters>::from_json(r#"{"parameters": {"segments": 0}}"#).is_err()
         );
-        let radius = sphere.radius as f64;
-        if distance < radius {
-            Some(Intersection {
-                sphere: sphere.clone(),
-                distance: 1000.0 + sphere.centre.z as f64
-                    - (radius.powf(2.0) - distance.powf(2.0)).powf(0.5)
-                    - 1000.0,
-            })
-        } else {
-            None
-        }
     }
-}
 
-fn beachball_orbit_radius(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => beachball_orbit_radius(a % 1.0 + 1.0),
-        x if x < 0.2 => 0.0,
-        x if x < 1.0 => 1.0 - positive_cos((a - 0.2) * 5.0 / 4.0 * TAU),
-        _ => beachball_orbit_radius(a % 1.0),
+    #[test]
+    fn neighbouring_segments_contrast_in_the_explanatory_scheme() {
+        let parameters = Parameters::default();
+        let greys = |scheme_name| {
+            let scheme = SchemeChoice::Named(scheme_name).scheme(&parameters);
+            coloured_frame(0.0, &parameters, &scheme)
+                .spheres
+                .iter()
+                .map(|sphere| sphere.grey_value)
+                .collect::<Vec<u8>>()
+        };
+        let explanatory = greys(SchemeName::Explanatory);
+        assert_ne!(explanatory, greys(SchemeName::Standard));
+        assert_eq!(explanatory, vec![48, 176, 64, 192, 80, 208, 96, 224]);
     }
-}
 
-fn positive_cos(a: f64) -> f64 {
-    (a.cos() + 1.0) * 0.5
+    #[test]
+    fn the_checks_shared_by_every_animation_pass() {
+        AnimationChecks {
+            name: NAME,
+            duration_seconds: DURATION_SECONDS,
+            scene_description: SCENE_DESCRIPTION,
+            #[cfg(feature = "script")]
+            script: SCRIPT,
+            coloured_frame,
+            render_frame,
+        }
+        .run();
+    }
 }
@@ -0,0 +1,131 @@
+use crate::contrast::contrast_scheme;
+use crate::palette::{Scheme, SchemeChoice, SchemeName};
+use crate::parameters::{self, AnimationParameters};
+use crate::scene::Scene;
+#[cfg(feature = "script")]
+use crate::script::Script;
+use crate::{Group, Point3d};
+
+/// The parts of an animation that every animation's tests check in the same way: that the scene
+/// description and the script reproduce it, that its schemes colour every sphere, that its schema
+/// matches its parameters, and that `render_frame` squeezes frames that are not square.
+pub struct AnimationChecks<P: AnimationParameters> {
+    pub name: &'static str,
+    pub duration_seconds: f64,
+    pub scene_description: &'static str,
+    #[cfg(feature = "script")]
+    pub script: &'static str,
+    /// The animation at a fraction of the way through, varied by the parameters and coloured by the
+    /// scheme.
+    pub coloured_frame: fn(f64, &P, &Scheme) -> Group,
+    pub render_frame: fn(&str, f64, &str) -> Result<Vec<u8>, String>,
+}
+
+impl<P: AnimationParameters> AnimationChecks<P> {
+    /// Run every check, panicking at the first that fails.
+    pub fn run(&self) {
+        self.standard_scheme_colours_every_sphere();
+        self.contrast_scheme_colours_every_sphere();
+        self.a_frame_that_is_not_square_is_the_square_frame_squeezed();
+        self.the_schema_describes_every_parameter_and_allows_its_default();
+        self.scene_description_reproduces_the_animation();
+        #[cfg(feature = "script")]
+        self.script_reproduces_the_animation();
+    }
+
+    fn standard_scheme_colours_every_sphere(&self) {
+        let scheme = SchemeChoice::Named(SchemeName::Standard).scheme(&P::default());
+        for sphere in (self.coloured_frame)(0.5, &P::default(), &Scheme::new(&[])).spheres {
+            assert!(
+                scheme.grey_value(&sphere).is_some(),
+                "{} has a grey in the standard scheme of the {}.",
+                sphere.label(0),
+                self.name
+            );
+        }
+    }
+
+    fn contrast_scheme_colours_every_sphere(&self) {
+        let assembled = (self.coloured_frame)(0.0, &P::default(), &Scheme::new(&[]));
+        let scheme = contrast_scheme(&assembled);
+        for sphere in assembled.spheres {
+            assert!(
+                scheme.grey_value(&sphere).is_some(),
+                "{} has a grey in the contrast scheme of the {}.",
+                sphere.label(0),
+                self.name
+            );
+        }
+    }
+
+    fn a_frame_that_is_not_square_is_the_square_frame_squeezed(&self) {
+        let render = |width: u32, height: u32| {
+            let options = format!(r#"{{"width": {width}, "height": {height}, "format": "raw"}}"#);
+            (self.render_frame)(self.name, 0.3, &options).expect("The options are valid.")
+        };
+        let square = render(200, 200);
+        let wide = render(200, 100);
+        assert_eq!(wide.len(), 200 * 100);
+        // Each row of the wide frame is every other row of the square frame
+        for (row, pixels) in wide.chunks(200).enumerate() {
+            assert_eq!(pixels, &square[400 * row..400 * row + 200]);
+        }
+        assert!((self.render_frame)(self.name, 0.3, r#"{"width": 5000}"#).is_err());
+    }
+
+    fn the_schema_describes_every_parameter_and_allows_its_default(&self) {
+        let defaults = serde_json::to_value(P::default()).expect("Parameters contain no maps.");
+        let mut fields: Vec<&String> = defaults
+            .as_object()
+            .expect("Parameters are an object.")
+            .keys()
+            .collect();
+        let mut names: Vec<&str> = P::SCHEMA.iter().map(|schema| schema.name).collect();
+        fields.sort();
+        names.sort();
+        assert_eq!(fields, names);
+        assert_eq!(parameters::validate(&P::default()), Ok(()));
+    }
+
+    fn scene_description_reproduces_the_animation(&self) {
+        let scene = Scene::from_json(self.scene_description).expect("The scene loads.");
+        assert_eq!(scene.duration_seconds, self.duration_seconds);
+        let parameters = P::default();
+        for scheme_name in [SchemeName::Standard, SchemeName::Explanatory] {
+            let scheme = SchemeChoice::Named(scheme_name).scheme(&parameters);
+            for frame_index in 0..=100 {
+                let animation_fraction = frame_index as f64 / 100.0;
+                assert_matches(
+                    &scene.frame(animation_fraction, scheme_name),
+                    &(self.coloured_frame)(animation_fraction, &parameters, &scheme),
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "script")]
+    fn script_reproduces_the_animation(&self) {
+        let script = Script::new(self.script).expect("The script runs.");
+        assert_eq!(script.duration_seconds, self.duration_seconds);
+        let parameters = P::default();
+        let scheme = SchemeChoice::default_for::<P>().scheme(&parameters);
+        for frame_index in 0..=100 {
+            let animation_fraction = frame_index as f64 / 100.0;
+            assert_matches(
+                &script.frame(animation_fraction).expect("The frame runs."),
+                &(self.coloured_frame)(animation_fraction, &parameters, &scheme),
+            );
+        }
+    }
+}
+
+// The scene and the script compute some angles in a different order from the Rust, so positions may
+// differ in the last bits
+fn assert_matches(actual: &Group, expected: &Group) {
+    assert_eq!(actual.spheres.len(), expected.spheres.len());
+    for (actual, expected) in actual.spheres.iter().zip(&expected.spheres) {
+        assert!(Point3d::distance(&actual.centre, &expected.centre) < 1e-12);
+        assert_eq!(actual.radius, expected.radius);
+        assert_eq!(actual.grey_value, expected.grey_value);
+    }
+}
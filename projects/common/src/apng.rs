@@ -0,0 +1,314 @@
+use crate::encoding::{Encoding, EncodingReport};
+use std::io::BufWriter;
+
+// When optimising, encodings are compared on one frame in this many
+const SAMPLE_EVERY: usize = 16;
+
+/// Collects the frames of an animated PNG and writes only what changes between them.
+///
+/// Each frame after the first is cropped to the rectangle containing every pixel that differs from
+/// the previous frame. That rectangle is drawn over the previous frame, so the file decodes to exactly
+/// the frames that were added. A frame identical to the previous one is not written at all; the
+/// previous frame is shown for longer instead, unless that would make its delay too long to store.
+pub struct Apng {
+    width: u32,
+    height: u32,
+    delay_denominator: u16,
+    loop_count: u32,
+    previous: Option<Vec<u8>>,
+    frames: Vec<SubFrame>,
+}
+
+#[derive(Clone)]
+struct SubFrame {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    delay_numerator: u16,
+}
+
+impl Apng {
+    /// Frame delays are given as numerators over `delay_denominator`, in seconds.
+    pub fn new(width: u32, height: u32, delay_denominator: u16) -> Self {
+        Self {
+            width,
+            height,
+            delay_denominator,
+            loop_count: 0,
+            previous: None,
+            frames: vec![],
+        }
+    }
+
+    /// Play the animation `loop_count` times before stopping on the last frame, or forever if it is 0,
+    /// which is the default.
+    pub fn set_loop_count(&mut self, loop_count: u32) {
+        self.loop_count = loop_count;
+    }
+
+    /// Add the full grayscale pixels of the next frame, to be shown for `delay_numerator` over the
+    /// delay denominator seconds.
+    pub fn add_frame(&mut self, pixels: Vec<u8>, delay_numerator: u16) {
+        let sub_frame = match &self.previous {
+            None => Some(SubFrame {
+                x: 0,
+                y: 0,
+                width: self.width,
+                height: self.height,
+                pixels: pixels.clone(),
+                delay_numerator,
+            }),
+            Some(previous) => self.changed_region(previous, &pixels, delay_numerator),
+        };
+        match sub_frame {
+            Some(sub_frame) => self.frames.push(sub_frame),
+            None => {
+                let last = self
+                    .frames
+                    .last_mut()
+                    .expect("The first frame is always kept.");
+                match last.delay_numerator.checked_add(delay_numerator) {
+                    Some(total) => last.delay_numerator = total,
+                    // The hold is too long for one delay, so it carries on in a frame that redraws a
+                    // single pixel as it was
+                    None => self.frames.push(SubFrame {
+                        x: 0,
+                        y: 0,
+                        width: 1,
+                        height: 1,
+                        pixels: vec![pixels[0]],
+                        delay_numerator,
+                    }),
+                }
+            }
+        }
+        self.previous = Some(pixels);
+    }
+
+    fn changed_region(
+        &self,
+        previous: &[u8],
+        pixels: &[u8],
+        delay_numerator: u16,
+    ) -> Option<SubFrame> {
+        let width = self.width as usize;
+        let mut left = width;
+        let mut right = 0;
+        let mut top = None;
+        let mut bottom = 0;
+        for (y, (previous_row, row)) in previous.chunks(width).zip(pixels.chunks(width)).enumerate()
+        {
+            let Some(first) = previous_row.iter().zip(row).position(|(a, b)| a != b) else {
+                continue;
+            };
+            let last = previous_row.iter().zip(row).rposition(|(a, b)| a != b);
+            left = left.min(first);
+            right = right.max(last.expect("There is a difference in this row."));
+            top.get_or_insert(y);
+            bottom = y;
+        }
+        let top = top?;
+
+        let pixels = pixels
+            .chunks(width)
+            .skip(top)
+            .take(bottom + 1 - top)
+            .flat_map(|row| row[left..=right].iter().copied())
+            .collect();
+        Some(SubFrame {
+            x: left as u32,
+            y: top as u32,
+            width: (right + 1 - left) as u32,
+            height: (bottom + 1 - top) as u32,
+            pixels,
+            delay_numerator,
+        })
+    }
+
+    /// Write the collected frames as an 8-bit grayscale APNG file.
+    pub fn encode(&self) -> Vec<u8> {
+        self.encode_with(&Encoding::default())
+    }
+
+    /// Write the collected frames as an APNG file, storing the pixels as `encoding` describes.
+    pub fn encode_with(&self, encoding: &Encoding) -> Vec<u8> {
+        let mut file = vec![];
+        {
+            // Start a block so that the APNG file will be closed after all of the frames have been written
+            let w = &mut BufWriter::new(&mut file);
+
+            let mut encoder = png::Encoder::new(w, self.width, self.height);
+            encoding.configure(&mut encoder);
+            match encoder.set_animated(self.frames.len() as u32, self.loop_count) {
+                Ok(_) => (),
+                Err(error) => eprintln!("encoder.set_animated result: {error}"),
+            };
+            let mut writer = encoder.write_header().unwrap();
+
+            for frame in &self.frames {
+                // The position is reset first so that the new dimensions are never checked against
+                // the previous frame's offset
+                writer.reset_frame_position().unwrap();
+                writer
+                    .set_frame_dimension(frame.width, frame.height)
+                    .unwrap();
+                writer.set_frame_position(frame.x, frame.y).unwrap();
+                writer
+                    .set_frame_delay(frame.delay_numerator, self.delay_denominator)
+                    .unwrap();
+                writer.set_dispose_op(png::DisposeOp::None).unwrap();
+                writer.set_blend_op(png::BlendOp::Source).unwrap();
+                writer
+                    .write_image_data(&encoding.pack(frame.width, &frame.pixels))
+                    .unwrap();
+            }
+        }
+        file
+    }
+
+    /// Write the frames with every candidate encoding for the grey values they contain, and choose
+    /// the one that gives the smallest file. The file is never larger than `encode` would write.
+    pub fn optimise(&self) -> Encoding {
+        // Writing every frame with every candidate takes several times as long as rendering, so the
+        // candidates are compared on a sample of the frames, and only the best is written in full
+        let sample = Self {
+            previous: None,
+            frames: self.frames.iter().step_by(SAMPLE_EVERY).cloned().collect(),
+            ..*self
+        };
+        let best = Encoding::candidates(&self.grey_values())
+            .into_iter()
+            .min_by_key(|encoding| sample.encode_with(encoding).len())
+            .expect("There is always at least one candidate.");
+        match self.encode_with(&best).len() <= self.encode().len() {
+            true => best,
+            false => Encoding::default(),
+        }
+    }
+
+    /// Compare the size of the file written as 8-bit grayscale with the size after optimising.
+    pub fn report(&self) -> EncodingReport {
+        let encoding = self.optimise();
+        EncodingReport {
+            unoptimised_bytes: self.encode().len(),
+            optimised_bytes: self.encode_with(&encoding).len(),
+            encoding: encoding.to_string(),
+        }
+    }
+
+    fn grey_values(&self) -> Vec<u8> {
+        let mut present = [false; 256];
+        for frame in &self.frames {
+            for pixel in &frame.pixels {
+                present[*pixel as usize] = true;
+            }
+        }
+        (0..=255)
+            .filter(|grey_value| present[*grey_value as usize])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: u32 = 8;
+    const HEIGHT: u32 = 6;
+
+    // Decode every frame of an APNG file, drawing each subframe over the previous frame, and return
+    // the full pixels of each frame with its delay numerator
+    fn decode(file: &[u8]) -> Vec<(Vec<u8>, u16)> {
+        let mut decoder = png::Decoder::new(file);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info().expect("The file has a valid header.");
+        let mut canvas = vec![0; (WIDTH * HEIGHT) as usize];
+        let mut frames = vec![];
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        while frames.len() < reader.info().animation_control.unwrap().num_frames as usize {
+            let output = reader.next_frame(&mut buffer).expect("The frame decodes.");
+            let control = reader
+                .info()
+                .frame_control
+                .expect("Every frame has a control.");
+            assert!(control.dispose_op == png::DisposeOp::None);
+            assert!(control.blend_op == png::BlendOp::Source);
+            let samples = output.color_type.samples();
+            for y in 0..control.height {
+                for x in 0..control.width {
+                    let index = ((y * control.width + x) as usize) * samples;
+                    canvas[((control.y_offset + y) * WIDTH + control.x_offset + x) as usize] =
+                        buffer[index];
+                }
+            }
+            frames.push((canvas.clone(), control.delay_num));
+        }
+        frames
+    }
+
+    fn frame_with(changes: &[(u32, u32, u8)]) -> Vec<u8> {
+        let mut pixels = vec![64; (WIDTH * HEIGHT) as usize];
+        for (x, y, grey_value) in changes {
+            pixels[(y * WIDTH + x) as usize] = *grey_value;
+        }
+        pixels
+    }
+
+    #[test]
+    fn the_frames_decode_to_the_pixels_they_were_given() {
+        let first = frame_with(&[(0, 0, 0), (7, 5, 255)]);
+        let moved = frame_with(&[(2, 1, 0), (5, 3, 255)]);
+        let corner = frame_with(&[(2, 1, 0), (5, 3, 255), (7, 0, 128)]);
+        let mut apng = Apng::new(WIDTH, HEIGHT, 1000);
+        apng.add_frame(first.clone(), 20);
+        apng.add_frame(moved.clone(), 20);
+        apng.add_frame(moved.clone(), 20);
+        apng.add_frame(moved.clone(), 20);
+        apng.add_frame(corner.clone(), 20);
+        apng.add_frame(first.clone(), 20);
+
+        // Only the changed rectangle is written after the first frame, and repeats lengthen the delay
+        assert!(apng.frames[0].width == WIDTH && apng.frames[0].height == HEIGHT);
+        assert_eq!(apng.frames.len(), 4);
+        assert_eq!((apng.frames[2].x, apng.frames[2].y), (7, 0));
+        assert_eq!((apng.frames[2].width, apng.frames[2].height), (1, 1));
+
+        let expected = vec![(first.clone(), 20), (moved, 60), (corner, 20), (first, 20)];
+        assert_eq!(decode(&apng.encode()), expected);
+        assert_eq!(decode(&apng.encode_with(&apng.optimise())), expected);
+    }
+
+    #[test]
+    fn a_hold_too_long_for_one_delay_carries_on_in_another_frame() {
+        let first = frame_with(&[(0, 0, 0)]);
+        let second = frame_with(&[(0, 0, 255)]);
+        let mut apng = Apng::new(WIDTH, HEIGHT, 1000);
+        apng.add_frame(first.clone(), 40000);
+        apng.add_frame(first.clone(), 20000);
+        apng.add_frame(first.clone(), 20000);
+        apng.add_frame(second.clone(), 20);
+        let expected = vec![(first.clone(), 60000), (first.clone(), 20000), (second, 20)];
+        assert_eq!(decode(&apng.encode()), expected);
+        assert_eq!(decode(&apng.encode_with(&apng.optimise())), expected);
+    }
+
+    #[test]
+    fn the_optimised_file_is_never_larger_than_the_default() {
+        let gradient: Vec<u8> = (0..WIDTH * HEIGHT).map(|index| (index * 5) as u8).collect();
+        let two_greys = frame_with(&[(3, 2, 255), (4, 2, 255)]);
+        let many_greys = frame_with(&[(1, 1, 7), (2, 3, 200), (6, 4, 33), (0, 5, 90)]);
+        for frames in [
+            vec![gradient.clone()],
+            vec![two_greys.clone(), frame_with(&[])],
+            vec![many_greys, two_greys, gradient],
+        ] {
+            let mut apng = Apng::new(WIDTH, HEIGHT, 1000);
+            for pixels in frames {
+                apng.add_frame(pixels, 20);
+            }
+            assert!(apng.encode_with(&apng.optimise()).len() <= apng.encode().len());
+        }
+    }
+}
@@ -0,0 +1,86 @@
+/// When each frame of an animation is shown, for an animation lasting `duration_seconds` rendered at
+/// `frames_per_second`.
+///
+/// Frames sample the animation at their own time, so the motion is the same at every frame rate.
+/// Each frame's delay is the difference between consecutive timestamps rounded to the millisecond,
+/// so rates that do not divide a second evenly (such as 60 fps) still add up to exactly the duration.
+pub struct Clock {
+    duration_seconds: f64,
+    frames_per_second: f64,
+}
+
+// APNG delays are a fraction, and every delay is given in milliseconds
+pub const DELAY_DENOMINATOR: u16 = 1000;
+
+impl Clock {
+    pub fn new(duration_seconds: f64, frames_per_second: f64) -> Option<Self> {
+        match (duration_seconds, frames_per_second) {
+            (d, f) if d.is_finite() && f.is_finite() && d > 0.0 && f > 0.0 => Some(Self {
+                duration_seconds,
+                frames_per_second,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.total_frames().round().max(1.0) as u32
+    }
+
+    pub fn animation_fraction(&self, frame_index: u32) -> f64 {
+        (frame_index as f64 / self.total_frames()).min(1.0)
+    }
+
+    /// The fraction of the animation that passes between one frame and the next.
+    pub fn frame_fraction(&self) -> f64 {
+        1.0 / self.total_frames()
+    }
+
+    /// How long the frame is shown, in thousandths of a second. A frame longer than the longest delay
+    /// an APNG can hold, just over 65 seconds, is shown for that long instead.
+    pub fn delay(&self, frame_index: u32) -> u16 {
+        let milliseconds = |frame_index: u32| {
+            (self.animation_fraction(frame_index) * self.duration_seconds * 1000.0).round() as u32
+        };
+        let end = match frame_index + 1 {
+            next if next >= self.frame_count() => (self.duration_seconds * 1000.0).round() as u32,
+            next => milliseconds(next),
+        };
+        (end - milliseconds(frame_index)).min(u16::MAX as u32) as u16
+    }
+
+    // The number of frames the duration would hold if it were not rounded to a whole frame
+    fn total_frames(&self) -> f64 {
+        self.duration_seconds * self.frames_per_second
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_delays_add_up_to_the_duration() {
+        for frames_per_second in [24.0, 30.0, 50.0, 60.0, 7.3, 0.5] {
+            for duration_seconds in [1.0, 2.2, 3.7, 10.0] {
+                let clock = Clock::new(duration_seconds, frames_per_second).unwrap();
+                let total: u32 = (0..clock.frame_count())
+                    .map(|frame_index| clock.delay(frame_index) as u32)
+                    .sum();
+                assert_eq!(
+                    total,
+                    (duration_seconds * 1000.0).round() as u32,
+                    "{duration_seconds} s at {frames_per_second} fps"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn a_frame_too_long_for_an_apng_delay_is_shortened() {
+        let clock = Clock::new(200.0, 0.01).unwrap();
+        assert_eq!(clock.frame_count(), 2);
+        assert_eq!(clock.delay(0), u16::MAX);
+        assert_eq!(clock.delay(1), u16::MAX);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::palette::Scheme;
+use crate::{Group, Point3d};
+use std::collections::{BTreeMap, BTreeSet};
+use std::f64::consts::TAU;
+
+// The width and height in pixels of each view used to find which spheres share a boundary
+const RESOLUTION: u32 = 256;
+// The number of views looking in towards the vertical axis, to which a view from above and a view
+// from below are added
+const VIEWS_AROUND: u32 = 8;
+// The range of the greys given out, darker than this being hard to tell apart from the background
+const DARKEST: u8 = 30;
+const LIGHTEST: u8 = 255;
+// Above this many greys there are too many ways to order them to try them all
+const MAX_ORDERED_GREYS: usize = 8;
+
+/// A colour scheme giving each named sphere of `group` an explanatory grey, so that spheres that share
+/// a visible boundary look as different as possible.
+///
+/// `group` is the ball in its assembled state, centred on the origin. It is seen from all round, each
+/// pixel recording the sphere nearest to the camera, and two spheres share a boundary wherever their
+/// pixels meet. The spheres are then coloured with DSatur, which uses few colours, and the colours are
+/// given greys spaced evenly from `DARKEST` to `LIGHTEST`, in whichever order keeps neighbouring greys
+/// furthest apart. A scheme picks out spheres by name, so spheres without a name are left out.
+pub fn contrast_scheme(group: &Group) -> Scheme {
+    let boundaries = boundaries(group);
+    let colours = dsatur(group.spheres.len(), &boundaries);
+    let greys = greys(&colours, &boundaries);
+    Scheme {
+        rules: group
+            .spheres
+            .iter()
+            .zip(colours)
+            .filter_map(|(sphere, colour)| Some((sphere.name.clone()?, greys[colour])))
+            .collect(),
+    }
+}
+
+// The number of pixels along the boundary between each pair of spheres that meet, keyed by their
+// indices with the lower first, over every view
+fn boundaries(group: &Group) -> BTreeMap<(usize, usize), u32> {
+    let mut boundaries = BTreeMap::new();
+    for view in views(group) {
+        let nearest = nearest_spheres(&view);
+        let index = |x: u32, y: u32| nearest[(y * RESOLUTION + x) as usize];
+        for y in 0..RESOLUTION {
+            for x in 0..RESOLUTION {
+                let neighbours = [
+                    (x + 1 < RESOLUTION).then(|| index(x + 1, y)),
+                    (y + 1 < RESOLUTION).then(|| index(x, y + 1)),
+                ];
+                for neighbour in neighbours.into_iter().flatten() {
+                    if let (Some(a), Some(b)) = (index(x, y), neighbour) {
+                        if a != b {
+                            *boundaries.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    boundaries
+}
+
+fn views(group: &Group) -> Vec<Group> {
+    let turn = |angle: f64, axis: &Point3d| {
+        group
+            .rotate(angle, axis)
+            .expect("The axis vectors are not zero.")
+    };
+    let mut views: Vec<Group> = (0..VIEWS_AROUND)
+        .map(|view| turn(TAU * view as f64 / VIEWS_AROUND as f64, &Point3d::y_axis()))
+        .collect();
+    views.push(turn(TAU / 4.0, &Point3d::x_axis()));
+    views.push(turn(-TAU / 4.0, &Point3d::x_axis()));
+    views
+}
+
+// The index of the sphere seen at each pixel, row by row, in the same way as `frame_pixels` finds
+// the sphere whose grey to use
+fn nearest_spheres(group: &Group) -> Vec<Option<usize>> {
+    let mut nearest = vec![];
+    for y in 0..RESOLUTION {
+        for x in 0..RESOLUTION {
+            let normalised_x = x as f64 / RESOLUTION as f64 * 2.0 - 1.0;
+            let normalised_y = y as f64 / RESOLUTION as f64 * 2.0 - 1.0;
+            let mut intersection =
+                crate::sphere_with_intersection_distance(normalised_x, normalised_y);
+            let result = group
+                .spheres
+                .iter()
+                .enumerate()
+                .filter_map(|(index, sphere)| {
+                    intersection(sphere).map(|intersection| (index, intersection.distance))
+                })
+                .reduce(|current, next| if next.1 < current.1 { next } else { current });
+            nearest.push(result.map(|(index, _)| index));
+        }
+    }
+    nearest
+}
+
+// A colour for each sphere, numbered from 0, such that spheres sharing a boundary have different
+// colours. DSatur colours one sphere at a time, choosing the sphere whose neighbours already have the
+// most different colours, breaking ties by the most neighbours still to colour, and gives it the
+// lowest colour that none of its neighbours has.
+fn dsatur(sphere_count: usize, boundaries: &BTreeMap<(usize, usize), u32>) -> Vec<usize> {
+    let mut neighbours = vec![BTreeSet::new(); sphere_count];
+    for &(a, b) in boundaries.keys() {
+        neighbours[a].insert(b);
+        neighbours[b].insert(a);
+    }
+    let mut colours: Vec<Option<usize>> = vec![None; sphere_count];
+    while let Some(next) = (0..sphere_count)
+        .filter(|&sphere| colours[sphere].is_none())
+        .max_by_key(|&sphere| {
+            let neighbour_colours: BTreeSet<usize> = neighbours[sphere]
+                .iter()
+                .filter_map(|&neighbour| colours[neighbour])
+                .collect();
+            let uncoloured = neighbours[sphere]
+                .iter()
+                .filter(|&&neighbour| colours[neighbour].is_none())
+                .count();
+            // The lowest index wins a complete tie, so the colouring does not depend on the order
+            // `max_by_key` visits equal keys in
+            (neighbour_colours.len(), uncoloured, sphere_count - sphere)
+        })
+    {
+        let taken: BTreeSet<usize> = neighbours[next]
+            .iter()
+            .filter_map(|&neighbour| colours[neighbour])
+            .collect();
+        colours[next] = (0..).find(|colour| !taken.contains(colour));
+    }
+    colours
+        .into_iter()
+        .map(|colour| colour.expect("Every sphere has been coloured."))
+        .collect()
+}
+
+// The grey for each colour. The greys are spaced evenly, and when there are few enough of them every
+// order is tried, keeping the one where the closest pair of neighbouring colours is furthest apart,
+// then the one where neighbouring colours differ most in total, weighted by the length of their
+// boundaries.
+fn greys(colours: &[usize], boundaries: &BTreeMap<(usize, usize), u32>) -> Vec<u8> {
+    let colour_count = colours.iter().max().map_or(0, |colour| colour + 1);
+    let levels: Vec<u8> = (0..colour_count)
+        .map(|level| match colour_count {
+            1 => LIGHTEST,
+            _ => (DARKEST as f64
+                + (LIGHTEST - DARKEST) as f64 * level as f64 / (colour_count - 1) as f64)
+                .round() as u8,
+        })
+        .collect();
+    if colour_count > MAX_ORDERED_GREYS {
+        return levels;
+    }
+    let mut shared = BTreeMap::new();
+    for (&(a, b), &length) in boundaries {
+        let (a, b) = (colours[a], colours[b]);
+        *shared.entry((a.min(b), a.max(b))).or_insert(0u64) += length as u64;
+    }
+    let score = |order: &[usize]| {
+        let differences = shared
+            .iter()
+            .map(|(&(a, b), &length)| (order[a].abs_diff(order[b]), length));
+        (
+            differences.clone().map(|(difference, _)| difference).min(),
+            differences
+                .map(|(difference, length)| difference as u64 * length)
+                .sum::<u64>(),
+        )
+    };
+    let mut best: Option<(Vec<usize>, _)> = None;
+    for order in orders(colour_count) {
+        let order_score = score(&order);
+        if best
+            .as_ref()
+            .is_none_or(|(_, best_score)| order_score > *best_score)
+        {
+            best = Some((order, order_score));
+        }
+    }
+    let (order, _) = best.expect("There is at least one order, even of no colours.");
+    order.into_iter().map(|level| levels[level]).collect()
+}
+
+// Every ordering of 0 to `count - 1`
+fn orders(count: usize) -> Vec<Vec<usize>> {
+    if count == 0 {
+        return vec![vec![]];
+    }
+    orders(count - 1)
+        .into_iter()
+        .flat_map(|order| {
+            (0..count).map(move |position| {
+                let mut order = order.clone();
+                order.insert(position, count - 1);
+                order
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(length: usize) -> BTreeMap<(usize, usize), u32> {
+        (0..length)
+            .map(|sphere| {
+                let next = (sphere + 1) % length;
+                ((sphere.min(next), sphere.max(next)), 1)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn an_even_ring_takes_two_colours() {
+        assert_eq!(dsatur(6, &ring(6)), vec![0, 1, 0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn an_odd_ring_takes_three_colours() {
+        let colours = dsatur(5, &ring(5));
+        assert_eq!(colours.iter().max(), Some(&2));
+        for (a, b) in ring(5).keys() {
+            assert_ne!(colours[*a], colours[*b]);
+        }
+    }
+
+    #[test]
+    fn spheres_with_no_neighbours_share_the_lightest_grey() {
+        let colours = dsatur(3, &BTreeMap::new());
+        assert_eq!(greys(&colours, &BTreeMap::new()), vec![LIGHTEST]);
+    }
+
+    #[test]
+    fn the_least_contrast_between_neighbours_is_as_large_as_possible() {
+        // A path of four colours: in the even order neighbours are only one step apart, but an
+        // order such as 1, 3, 0, 2 keeps every pair of neighbours at least two steps apart
+        let boundaries = BTreeMap::from([((0, 1), 1), ((1, 2), 1), ((2, 3), 1)]);
+        let greys = greys(&[0, 1, 2, 3], &boundaries);
+        let step = (LIGHTEST - DARKEST) / 3;
+        for (a, b) in boundaries.keys() {
+            assert!(greys[*a].abs_diff(greys[*b]) >= 2 * step);
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::f64::consts::TAU;
+
+/// The shape of a transition from one value to another.
+///
+/// `ease` maps progress through the transition, from 0.0 to 1.0, to the fraction of the change made
+/// so far. Every easing maps 0.0 to 0.0 and 1.0 to 1.0, but may overshoot in between.
+pub trait Easing {
+    fn ease(&self, progress: f64) -> f64;
+}
+
+/// Half a period of a cosine, starting and finishing at rest. This is the easing used by all of the
+/// original animations.
+pub struct PositiveCos;
+
+impl Easing for PositiveCos {
+    fn ease(&self, progress: f64) -> f64 {
+        1.0 - positive_cos(progress * TAU / 2.0)
+    }
+}
+
+pub struct Linear;
+
+impl Easing for Linear {
+    fn ease(&self, progress: f64) -> f64 {
+        progress
+    }
+}
+
+pub struct Smoothstep;
+
+impl Easing for Smoothstep {
+    fn ease(&self, progress: f64) -> f64 {
+        progress * progress * (3.0 - 2.0 * progress)
+    }
+}
+
+pub struct Smootherstep;
+
+impl Easing for Smootherstep {
+    fn ease(&self, progress: f64) -> f64 {
+        progress.powi(3) * (progress * (progress * 6.0 - 15.0) + 10.0)
+    }
+}
+
+pub struct CubicInOut;
+
+impl Easing for CubicInOut {
+    fn ease(&self, progress: f64) -> f64 {
+        if progress < 0.5 {
+            4.0 * progress.powi(3)
+        } else {
+            1.0 - (2.0 - 2.0 * progress).powi(3) / 2.0
+        }
+    }
+}
+
+pub struct QuinticInOut;
+
+impl Easing for QuinticInOut {
+    fn ease(&self, progress: f64) -> f64 {
+        if progress < 0.5 {
+            16.0 * progress.powi(5)
+        } else {
+            1.0 - (2.0 - 2.0 * progress).powi(5) / 2.0
+        }
+    }
+}
+
+/// Pulls back before setting off and overshoots before settling, by an amount set by `overshoot`
+/// (1.70158 is the conventional value).
+pub struct Back {
+    pub overshoot: f64,
+}
+
+impl Easing for Back {
+    fn ease(&self, progress: f64) -> f64 {
+        let c = self.overshoot * 1.525;
+        if progress < 0.5 {
+            (2.0 * progress).powi(2) * ((c + 1.0) * 2.0 * progress - c) / 2.0
+        } else {
+            ((2.0 * progress - 2.0).powi(2) * ((c + 1.0) * (2.0 * progress - 2.0) + c) + 2.0) / 2.0
+        }
+    }
+}
+
+/// Springs past the target and oscillates about it, with `oscillations` swings before settling.
+pub struct Elastic {
+    pub oscillations: f64,
+}
+
+impl Easing for Elastic {
+    fn ease(&self, progress: f64) -> f64 {
+        match progress {
+            x if x <= 0.0 => 0.0,
+            x if x >= 1.0 => 1.0,
+            _ => {
+                2.0_f64.powf(-10.0 * progress) * ((progress * self.oscillations - 0.25) * TAU).sin()
+                    + 1.0
+            }
+        }
+    }
+}
+
+/// Falls onto the target and bounces to rest.
+pub struct Bounce;
+
+impl Easing for Bounce {
+    fn ease(&self, progress: f64) -> f64 {
+        let n = 7.5625;
+        let d = 2.75;
+        match progress {
+            x if x < 1.0 / d => n * x * x,
+            x if x < 2.0 / d => n * (x - 1.5 / d).powi(2) + 0.75,
+            x if x < 2.5 / d => n * (x - 2.25 / d).powi(2) + 0.9375,
+            x => n * (x - 2.625 / d).powi(2) + 0.984375,
+        }
+    }
+}
+
+/// A cubic Bézier curve from (0, 0) to (1, 1) with control points (x1, y1) and (x2, y2), as used by
+/// CSS `cubic-bezier()`. The x coordinates must lie between 0.0 and 1.0.
+pub struct CubicBezier {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+}
+
+impl CubicBezier {
+    fn coordinate(a: f64, b: f64, s: f64) -> f64 {
+        3.0 * (1.0 - s).powi(2) * s * a + 3.0 * (1.0 - s) * s * s * b + s.powi(3)
+    }
+
+    fn slope(a: f64, b: f64, s: f64) -> f64 {
+        3.0 * (1.0 - s).powi(2) * a + 6.0 * (1.0 - s) * s * (b - a) + 3.0 * s * s * (1.0 - b)
+    }
+}
+
+impl Easing for CubicBezier {
+    fn ease(&self, progress: f64) -> f64 {
+        // Find the curve parameter whose x coordinate is the progress, by Newton's method while
+        // the slope allows and by bisection otherwise
+        let mut s = progress;
+        for _ in 0..8 {
+            let error = Self::coordinate(self.x1, self.x2, s) - progress;
+            let slope = Self::slope(self.x1, self.x2, s);
+            if error.abs() < 1e-12 || slope.abs() < 1e-6 {
+                break;
+            }
+            s -= error / slope;
+        }
+        if (Self::coordinate(self.x1, self.x2, s) - progress).abs() > 1e-9 {
+            let (mut low, mut high) = (0.0, 1.0);
+            s = progress;
+            for _ in 0..64 {
+                if Self::coordinate(self.x1, self.x2, s) < progress {
+                    low = s;
+                } else {
+                    high = s;
+                }
+                s = (low + high) / 2.0;
+            }
+        }
+        Self::coordinate(self.y1, self.y2, s)
+    }
+}
+
+/// Look up an easing by name. Back and elastic take their conventional parameters, and a custom Bézier
+/// is written as in CSS, for example `cubic-bezier(0.25, 0.1, 0.25, 1)`.
+pub fn by_name(name: &str) -> Option<Box<dyn Easing>> {
+    let easing: Box<dyn Easing> = match name.trim() {
+        "positive-cos" => Box::new(PositiveCos),
+        "linear" => Box::new(Linear),
+        "smoothstep" => Box::new(Smoothstep),
+        "smootherstep" => Box::new(Smootherstep),
+        "cubic-in-out" => Box::new(CubicInOut),
+        "quintic-in-out" => Box::new(QuinticInOut),
+        "back" => Box::new(Back { overshoot: 1.70158 }),
+        "elastic" => Box::new(Elastic {
+            oscillations: 10.0 / 3.0,
+        }),
+        "bounce" => Box::new(Bounce),
+        other => {
+            let arguments = other
+                .strip_prefix("cubic-bezier(")?
+                .strip_suffix(')')?
+                .split(',')
+                .map(|argument| argument.trim().parse::<f64>().ok())
+                .collect::<Option<Vec<f64>>>()?;
+            let [x1, y1, x2, y2] = arguments[..] else {
+                return None;
+            };
+            if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+                return None;
+            }
+            Box::new(CubicBezier { x1, y1, x2, y2 })
+        }
+    };
+    Some(easing)
+}
+
+pub fn positive_cos(a: f64) -> f64 {
+    (a.cos() + 1.0) * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: [&str; 10] = [
+        "positive-cos",
+        "linear",
+        "smoothstep",
+        "smootherstep",
+        "cubic-in-out",
+        "quintic-in-out",
+        "back",
+        "elastic",
+        "bounce",
+        "cubic-bezier(0.25, 0.1, 0.25, 1)",
+    ];
+
+    fn progress_steps() -> impl Iterator<Item = f64> {
+        (0..=1000).map(|step| step as f64 / 1000.0)
+    }
+
+    #[test]
+    fn every_easing_starts_at_0_and_finishes_at_1() {
+        for name in NAMES {
+            let easing = by_name(name).unwrap();
+            assert!(easing.ease(0.0).abs() < 1e-12, "{name} starts at 0.");
+            assert!(
+                (easing.ease(1.0) - 1.0).abs() < 1e-12,
+                "{name} finishes at 1."
+            );
+        }
+    }
+
+    #[test]
+    fn easings_that_do_not_overshoot_never_turn_back() {
+        // Back, elastic and bounce overshoot or bounce on purpose, so they are left out
+        for name in &NAMES[..6] {
+            let easing = by_name(name).unwrap();
+            let values: Vec<f64> = progress_steps()
+                .map(|progress| easing.ease(progress))
+                .collect();
+            assert!(
+                values.windows(2).all(|pair| pair[0] <= pair[1] + 1e-12),
+                "{name} never turns back."
+            );
+        }
+    }
+
+    #[test]
+    fn a_cubic_bezier_with_matching_coordinates_is_linear() {
+        // With y matching x at both control points, the curve is the diagonal and the eased value is
+        // the progress only if the solver found the right curve parameter. The control points include
+        // ones that make the slope zero at the ends or in the middle, where Newton's method stalls.
+        for (x1, x2) in [
+            (0.25, 0.75),
+            (0.0, 1.0),
+            (1.0, 0.0),
+            (0.0, 0.0),
+            (1.0, 1.0),
+            (0.9, 0.1),
+        ] {
+            let easing = CubicBezier {
+                x1,
+                y1: x1,
+                x2,
+                y2: x2,
+            };
+            for progress in progress_steps() {
+                assert!(
+                    (easing.ease(progress) - progress).abs() < 1e-9,
+                    "cubic-bezier({x1}, {x1}, {x2}, {x2}) at {progress} is {}.",
+                    easing.ease(progress)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn names_that_are_not_easings_are_rejected() {
+        for name in [
+            "",
+            "ease",
+            "cubic-bezier(0.25, 0.1, 0.25)",
+            "cubic-bezier(1.5, 0, 0.5, 1)",
+            "cubic-bezier(a, 0, 0.5, 1)",
+        ] {
+            assert!(by_name(name).is_none(), "{name:?} is not an easing.");
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use serde::Serialize;
+use std::fmt;
+
+/// How the pixels of a PNG are stored and compressed.
+///
+/// Every encoding is lossless. The frames only ever contain a handful of grey values, so they can
+/// often be stored in fewer bits per pixel, either as grayscale (when every grey value is a multiple
+/// of the step between levels) or as indices into a palette. Which of these, and which filter,
+/// compresses smallest depends on the frames, so `Apng::optimise` tries them all.
+#[derive(Clone)]
+pub struct Encoding {
+    colour: Colour,
+    filter: png::FilterType,
+    adaptive_filter: png::AdaptiveFilterType,
+    compression: png::Compression,
+}
+
+#[derive(Clone)]
+enum Colour {
+    Grayscale { bit_depth: u8 },
+    Indexed { palette: Vec<u8>, bit_depth: u8 },
+}
+
+impl Encoding {
+    /// Every lossless way of storing frames containing only `grey_values`: each colour type and bit
+    /// depth that can hold them, with each filter and the best compression, and the default encoding.
+    pub fn candidates(grey_values: &[u8]) -> Vec<Self> {
+        let mut colours = vec![Colour::Grayscale { bit_depth: 8 }];
+        if let Some(bit_depth) = [1, 2, 4].into_iter().find(|bit_depth| {
+            let step = 255 / ((1 << bit_depth) - 1);
+            grey_values.iter().all(|grey_value| grey_value % step == 0)
+        }) {
+            colours.push(Colour::Grayscale { bit_depth });
+        }
+        let bit_depth = [1, 2, 4, 8]
+            .into_iter()
+            .find(|bit_depth| grey_values.len() <= 1 << bit_depth)
+            .expect("There are at most 256 grey values.");
+        colours.push(Colour::Indexed {
+            palette: grey_values.to_vec(),
+            bit_depth,
+        });
+        let filters = [
+            png::FilterType::NoFilter,
+            png::FilterType::Sub,
+            png::FilterType::Up,
+            png::FilterType::Avg,
+            png::FilterType::Paeth,
+        ]
+        .map(|filter| (filter, png::AdaptiveFilterType::NonAdaptive));
+        let adaptive = (
+            png::FilterType::default(),
+            png::AdaptiveFilterType::Adaptive,
+        );
+        let mut candidates = vec![Self::default()];
+        for colour in colours {
+            for (filter, adaptive_filter) in filters.into_iter().chain([adaptive]) {
+                candidates.push(Self {
+                    colour: colour.clone(),
+                    filter,
+                    adaptive_filter,
+                    compression: png::Compression::Best,
+                });
+            }
+        }
+        candidates
+    }
+
+    pub fn configure<W: std::io::Write>(&self, encoder: &mut png::Encoder<W>) {
+        match &self.colour {
+            Colour::Grayscale { bit_depth } => {
+                encoder.set_color(png::ColorType::Grayscale);
+                encoder.set_depth(png::BitDepth::from_u8(*bit_depth).expect("The depth is valid."));
+            }
+            Colour::Indexed { palette, bit_depth } => {
+                encoder.set_color(png::ColorType::Indexed);
+                encoder.set_depth(png::BitDepth::from_u8(*bit_depth).expect("The depth is valid."));
+                encoder.set_palette(
+                    palette
+                        .iter()
+                        .flat_map(|grey_value| [*grey_value; 3])
+                        .collect::<Vec<u8>>(),
+                );
+            }
+        }
+        encoder.set_filter(self.filter);
+        encoder.set_adaptive_filter(self.adaptive_filter);
+        encoder.set_compression(self.compression);
+    }
+
+    /// Convert 8-bit grey values into packed samples, `width` pixels to a row.
+    pub fn pack(&self, width: u32, pixels: &[u8]) -> Vec<u8> {
+        let mut samples = [0; 256];
+        let bit_depth = match &self.colour {
+            Colour::Grayscale { bit_depth } => {
+                let step = 255 / ((1u16 << bit_depth) - 1) as u8;
+                for (grey_value, sample) in samples.iter_mut().enumerate() {
+                    *sample = grey_value as u8 / step;
+                }
+                *bit_depth
+            }
+            Colour::Indexed { palette, bit_depth } => {
+                for (index, grey_value) in palette.iter().enumerate() {
+                    samples[*grey_value as usize] = index as u8;
+                }
+                *bit_depth
+            }
+        };
+        if bit_depth == 8 {
+            return pixels
+                .iter()
+                .map(|pixel| samples[*pixel as usize])
+                .collect();
+        }
+
+        let per_byte = 8 / bit_depth as usize;
+        let mut packed = vec![];
+        for row in pixels.chunks(width as usize) {
+            for byte_pixels in row.chunks(per_byte) {
+                let mut byte = 0;
+                for (position, pixel) in byte_pixels.iter().enumerate() {
+                    let shift = 8 - bit_depth as usize * (position + 1);
+                    byte |= samples[*pixel as usize] << shift;
+                }
+                packed.push(byte);
+            }
+        }
+        packed
+    }
+}
+
+impl Default for Encoding {
+    /// 8-bit grayscale with the encoder's default filter and compression.
+    fn default() -> Self {
+        Self {
+            colour: Colour::Grayscale { bit_depth: 8 },
+            filter: png::FilterType::default(),
+            adaptive_filter: png::AdaptiveFilterType::default(),
+            compression: png::Compression::default(),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.colour {
+            Colour::Grayscale { bit_depth } => write!(f, "{bit_depth}-bit grayscale")?,
+            Colour::Indexed { palette, bit_depth } => {
+                write!(f, "{bit_depth}-bit palette of {} greys", palette.len())?
+            }
+        };
+        let filter = match self.adaptive_filter {
+            png::AdaptiveFilterType::Adaptive => "adaptive".to_string(),
+            png::AdaptiveFilterType::NonAdaptive => format!("{:?}", self.filter),
+        };
+        write!(f, ", {filter} filter, {:?} compression", self.compression)
+    }
+}
+
+#[derive(Serialize)]
+pub struct EncodingReport {
+    pub unoptimised_bytes: usize,
+    pub optimised_bytes: usize,
+    pub encoding: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An odd width, so that rows packed below 8 bits per pixel end part way through a byte
+    const WIDTH: u32 = 7;
+    const HEIGHT: u32 = 3;
+
+    // Write a single image with `encoding` and decode it back to 8-bit grey values
+    fn round_trip(encoding: &Encoding, pixels: &[u8]) -> Vec<u8> {
+        let mut file = vec![];
+        {
+            let mut encoder = png::Encoder::new(&mut file, WIDTH, HEIGHT);
+            encoding.configure(&mut encoder);
+            let mut writer = encoder.write_header().unwrap();
+            writer
+                .write_image_data(&encoding.pack(WIDTH, pixels))
+                .unwrap();
+        }
+        let mut decoder = png::Decoder::new(&file[..]);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let output = reader.next_frame(&mut buffer).unwrap();
+        buffer
+            .chunks(output.color_type.samples())
+            .take((WIDTH * HEIGHT) as usize)
+            .map(|samples| samples[0])
+            .collect()
+    }
+
+    fn pixels_using(grey_values: &[u8]) -> Vec<u8> {
+        (0..WIDTH * HEIGHT)
+            .map(|index| grey_values[(index * 5) as usize % grey_values.len()])
+            .collect()
+    }
+
+    #[test]
+    fn every_candidate_decodes_to_the_same_pixels() {
+        let grey_value_sets: [&[u8]; 8] = [
+            &[0, 255],
+            &[0, 85, 170, 255],
+            &[0, 17, 51, 119, 204, 255],
+            &[10, 200],
+            &[3, 100, 250],
+            &[1, 2, 3, 4, 5],
+            &[
+                0, 7, 14, 21, 28, 35, 42, 49, 56, 63, 70, 77, 84, 91, 98, 105, 112,
+            ],
+            &[64],
+        ];
+        for grey_values in grey_value_sets {
+            let pixels = pixels_using(grey_values);
+            for encoding in Encoding::candidates(grey_values) {
+                assert_eq!(round_trip(&encoding, &pixels), pixels, "{encoding}");
+            }
+        }
+    }
+
+    #[test]
+    fn the_fewest_bits_per_pixel_are_among_the_candidates() {
+        let colours = |grey_values: &[u8]| {
+            let mut colours: Vec<String> = Encoding::candidates(grey_values)
+                .iter()
+                .map(|encoding| {
+                    let encoding = encoding.to_string();
+                    encoding[..encoding.find(',').unwrap()].to_string()
+                })
+                .collect();
+            colours.dedup();
+            colours
+        };
+        assert_eq!(
+            colours(&[0, 255]),
+            [
+                "8-bit grayscale",
+                "1-bit grayscale",
+                "1-bit palette of 2 greys"
+            ]
+        );
+        assert_eq!(
+            colours(&[0, 85, 255]),
+            [
+                "8-bit grayscale",
+                "2-bit grayscale",
+                "2-bit palette of 3 greys"
+            ]
+        );
+        assert_eq!(
+            colours(&[0, 17, 34, 51, 255]),
+            [
+                "8-bit grayscale",
+                "4-bit grayscale",
+                "4-bit palette of 5 greys"
+            ]
+        );
+        assert_eq!(
+            colours(&[3, 100, 250]),
+            ["8-bit grayscale", "2-bit palette of 3 greys"]
+        );
+        assert_eq!(
+            colours(&(0..=255).collect::<Vec<u8>>()),
+            ["8-bit grayscale", "8-bit palette of 256 greys"]
+        );
+    }
+}
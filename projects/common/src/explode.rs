@@ -0,0 +1,316 @@
+use crate::easing;
+use crate::timeline::{Keyframe, Timeline};
+use crate::{Group, Point3d, Sphere};
+use serde::Deserialize;
+
+/// How to take an assembled group apart and put it back together, read from JSON. Every field is
+/// optional, and an empty string gives the defaults.
+///
+/// For example, `{"stagger": "outside_in", "stagger_spread": 0.6, "easing": "back"}` peels the spheres
+/// off one at a time from the outside in, overshooting a little as each one arrives.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExplodeOptions {
+    pub duration_seconds: f64,
+    /// How far each sphere moves out from where it sits in the assembled group, in multiples of its
+    /// own radius.
+    pub distance: f64,
+    pub stagger: Stagger,
+    /// How much of the time spent moving the starts are spread over, from 0.0 for every sphere
+    /// setting off at once up to, but not including, 1.0 for each sphere setting off as the one before
+    /// it arrives.
+    pub stagger_spread: f64,
+    /// The fraction of the loop spent assembled, at its start.
+    pub hold_assembled: f64,
+    /// The fraction of the loop spent apart, between moving out and moving back.
+    pub hold_apart: f64,
+    /// The name of an easing, as `easing::by_name` reads it. The default is `positive-cos`.
+    pub easing: Option<String>,
+    /// The point the spheres move out from. The default is the centre of the group, the mean of the
+    /// centres of its spheres.
+    pub centre: Option<Point3d>,
+}
+
+/// The order in which the spheres set off. They come back in the opposite order, so the last sphere
+/// out is the first back in.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Stagger {
+    /// The order of the spheres in the group.
+    InOrder,
+    /// The reverse of the order of the spheres in the group.
+    Reverse,
+    /// The sphere that reaches furthest from the centre first, so that the ball comes apart layer by
+    /// layer.
+    OutsideIn,
+}
+
+impl Default for ExplodeOptions {
+    fn default() -> Self {
+        Self {
+            duration_seconds: 8.0,
+            distance: 3.0,
+            stagger: Stagger::InOrder,
+            stagger_spread: 0.0,
+            hold_assembled: 0.2,
+            hold_apart: 0.2,
+            easing: None,
+            centre: None,
+        }
+    }
+}
+
+impl ExplodeOptions {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let options: Self = serde_json::from_str(json).map_err(|error| error.to_string())?;
+        options.validate()?;
+        Ok(options)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.duration_seconds.is_finite() && self.duration_seconds > 0.0) {
+            return Err("The duration is a positive number of seconds.".to_string());
+        }
+        if !(self.distance.is_finite() && self.distance >= 0.0) {
+            return Err("The distance is a number of at least 0.".to_string());
+        }
+        if !(0.0..1.0).contains(&self.stagger_spread) {
+            return Err("The stagger spread is from 0.0 up to 1.0.".to_string());
+        }
+        if !(self.hold_assembled >= 0.0
+            && self.hold_apart >= 0.0
+            && self.hold_assembled + self.hold_apart < 1.0)
+        {
+            return Err("The holds are at least 0.0 and add up to less than 1.0.".to_string());
+        }
+        self.easing()?;
+        Ok(())
+    }
+
+    fn easing(&self) -> Result<Box<dyn easing::Easing>, String> {
+        match &self.easing {
+            Some(easing) => {
+                easing::by_name(easing).ok_or(format!("There is no easing called \"{easing}\"."))
+            }
+            None => Ok(Box::new(easing::PositiveCos)),
+        }
+    }
+}
+
+/// An assembled group that comes apart and goes back together over one loop.
+///
+/// Each sphere moves straight out from the centre of the group, along the direction of its own centre
+/// from there, so spheres that are nested with slightly different centres fly apart in different
+/// directions. A sphere exactly at the centre has no direction to move in, and stays where it is.
+pub struct Explode {
+    assembled: Group,
+    moves: Vec<Move>,
+    options: ExplodeOptions,
+}
+
+// How one sphere moves: the direction it moves in, if any, and how far along it is at each keyframe,
+// from 0.0 when assembled to 1.0 when apart
+struct Move {
+    direction: Option<Point3d>,
+    keyframes: Vec<Keyframe>,
+}
+
+impl Explode {
+    pub fn new(assembled: Group, options: ExplodeOptions) -> Result<Self, String> {
+        options.validate()?;
+        let count = assembled.spheres.len();
+        let centre = options.centre.clone().unwrap_or_else(|| {
+            assembled
+                .spheres
+                .iter()
+                .fold(Point3d::origin(), |sum, sphere| sum + sphere.centre.clone())
+                * (1.0 / count.max(1) as f64)
+        });
+        let mut order: Vec<usize> = (0..count).collect();
+        match options.stagger {
+            Stagger::InOrder => {}
+            Stagger::Reverse => order.reverse(),
+            Stagger::OutsideIn => {
+                let reach =
+                    |sphere: &Sphere| Point3d::distance(&sphere.centre, &centre) + sphere.radius;
+                order.sort_by(|a, b| {
+                    reach(&assembled.spheres[*b]).total_cmp(&reach(&assembled.spheres[*a]))
+                });
+            }
+        }
+        let moving = (1.0 - options.hold_assembled - options.hold_apart) / 2.0;
+        let each_move = moving * (1.0 - options.stagger_spread);
+        let step = match count {
+            0 | 1 => 0.0,
+            _ => moving * options.stagger_spread / (count - 1) as f64,
+        };
+        let mut moves: Vec<Move> = assembled
+            .spheres
+            .iter()
+            .map(|sphere| Move {
+                direction: (sphere.centre.clone() - centre.clone()).normalise(),
+                keyframes: vec![],
+            })
+            .collect();
+        for (rank, index) in order.into_iter().enumerate() {
+            let out = options.hold_assembled + step * rank as f64;
+            let back = options.hold_assembled
+                + moving
+                + options.hold_apart
+                + step * (count - 1 - rank) as f64;
+            // Rounding may carry the end of the last move just past the end of the loop
+            let times = [
+                0.0,
+                out,
+                out + each_move,
+                back,
+                (back + each_move).min(1.0),
+                1.0,
+            ];
+            let values = [0.0, 0.0, 1.0, 1.0, 0.0, 0.0];
+            moves[index].keyframes = times
+                .into_iter()
+                .zip(values)
+                .map(|(time, value)| Keyframe::new(time, value))
+                .collect();
+        }
+        Ok(Self {
+            assembled,
+            moves,
+            options,
+        })
+    }
+
+    pub fn duration_seconds(&self) -> f64 {
+        self.options.duration_seconds
+    }
+
+    pub fn frame(&self, animation_fraction: f64) -> Group {
+        Group::new(
+            self.assembled
+                .spheres
+                .iter()
+                .zip(&self.moves)
+                .map(|(sphere, sphere_move)| match &sphere_move.direction {
+                    Some(direction) => Sphere {
+                        centre: sphere.centre.clone()
+                            + direction.clone()
+                                * (self.options.distance
+                                    * sphere.radius
+                                    * self.timeline(sphere_move).sample(animation_fraction)),
+                        ..sphere.clone()
+                    },
+                    None => sphere.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    /// How far each sphere has moved out, from 0.0 to 1.0, over the loop, named after the sphere.
+    pub fn tracks(&self) -> impl Iterator<Item = (String, Timeline)> + '_ {
+        self.assembled
+            .spheres
+            .iter()
+            .zip(&self.moves)
+            .enumerate()
+            .map(|(index, (sphere, sphere_move))| (sphere.label(index), self.timeline(sphere_move)))
+    }
+
+    fn timeline(&self, sphere_move: &Move) -> Timeline {
+        Timeline::new(sphere_move.keyframes.clone()).with_easing(
+            self.options
+                .easing()
+                .expect("The easing was checked when the options were validated."),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::ContinuityReport;
+
+    fn nested() -> Group {
+        let sphere = |x: f64, radius: f64| Sphere {
+            centre: Point3d { x, y: 0.0, z: 0.0 },
+            radius,
+            grey_value: 0,
+            name: None,
+            tags: Default::default(),
+        };
+        Group::new(vec![
+            sphere(0.0, 1.0),
+            sphere(0.002, 0.999),
+            sphere(-0.002, 1.001),
+        ])
+    }
+
+    #[test]
+    fn spheres_move_out_along_their_own_directions_and_back() {
+        let explode =
+            Explode::new(nested(), ExplodeOptions::default()).expect("The options are valid.");
+        let apart = explode.frame(0.5);
+        assert!(apart.spheres[0].centre == Point3d::origin());
+        assert!((apart.spheres[1].centre.x - (0.002 + 3.0 * 0.999)).abs() < 1e-12);
+        assert!((apart.spheres[2].centre.x - (-0.002 - 3.0 * 1.001)).abs() < 1e-12);
+        let report = ContinuityReport::new(explode.tracks(), |animation_fraction| {
+            explode.frame(animation_fraction)
+        });
+        for track in report.tracks {
+            assert_eq!(
+                track.discontinuities,
+                vec![],
+                "{} is continuous.",
+                track.track
+            );
+        }
+        assert_eq!(report.unmatched_at_loop, Vec::<String>::new());
+    }
+
+    #[test]
+    fn a_translated_group_comes_apart_in_the_same_way() {
+        let offset = Point3d {
+            x: 5.0,
+            y: -2.0,
+            z: 0.5,
+        };
+        let explode =
+            Explode::new(nested(), ExplodeOptions::default()).expect("The options are valid.");
+        let translated = Explode::new(nested() + offset.clone(), ExplodeOptions::default())
+            .expect("The options are valid.");
+        for frame_index in 0..=20 {
+            let animation_fraction = frame_index as f64 / 20.0;
+            let expected = explode.frame(animation_fraction) + offset.clone();
+            let actual = translated.frame(animation_fraction);
+            for (actual, expected) in actual.spheres.iter().zip(&expected.spheres) {
+                assert!(Point3d::distance(&actual.centre, &expected.centre) < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn the_outermost_sphere_sets_off_first() {
+        let options = ExplodeOptions {
+            stagger: Stagger::OutsideIn,
+            stagger_spread: 0.9,
+            ..ExplodeOptions::default()
+        };
+        let explode = Explode::new(nested(), options).expect("The options are valid.");
+        let setting_off = explode.frame(0.25);
+        assert!(setting_off.spheres[2].centre.x < -0.01);
+        assert_eq!(setting_off.spheres[1].centre.x, 0.002);
+    }
+
+    #[test]
+    fn options_that_leave_no_time_to_move_are_rejected() {
+        assert!(
+            ExplodeOptions::from_json(r#"{"hold_assembled": 0.5, "hold_apart": 0.5}"#).is_err()
+        );
+        assert!(ExplodeOptions::from_json(r#"{"stagger_spread": 1.0}"#).is_err());
+        assert!(ExplodeOptions::from_json(r#"{"easing": "wobbly"}"#).is_err());
+        assert!(ExplodeOptions::from_json("").is_ok());
+    }
+}
@@ -0,0 +1,54 @@
+use crate::palette::SchemeChoice;
+use crate::parameters::{self, AnimationParameters};
+use serde::Deserialize;
+
+// The largest width or height, which keeps a raw frame to 16 MiB
+pub const MAX_SIZE: u32 = 4096;
+
+/// How to render a single frame, read from JSON. Every field is optional, and an empty string gives
+/// the defaults: a full size PNG in the animation's default colour scheme.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields, bound = "P: AnimationParameters")]
+pub struct FrameOptions<P> {
+    pub width: u32,
+    pub height: u32,
+    pub format: FrameFormat,
+    pub colour_scheme: SchemeChoice,
+    pub parameters: P,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameFormat {
+    /// An encoded grayscale PNG file.
+    #[default]
+    Png,
+    /// One 8-bit grey value per pixel, row by row from the top left.
+    Raw,
+}
+
+impl<P: AnimationParameters> Default for FrameOptions<P> {
+    fn default() -> Self {
+        Self {
+            width: crate::WIDTH,
+            height: crate::HEIGHT,
+            format: FrameFormat::default(),
+            colour_scheme: SchemeChoice::default_for::<P>(),
+            parameters: P::default(),
+        }
+    }
+}
+
+impl<P: AnimationParameters> FrameOptions<P> {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let options: Self = serde_json::from_str(json).map_err(|error| error.to_string())?;
+        if !(1..=MAX_SIZE).contains(&options.width) || !(1..=MAX_SIZE).contains(&options.height) {
+            return Err(format!("The width and height are from 1 to {MAX_SIZE}."));
+        }
+        parameters::validate(&options.parameters)?;
+        Ok(options)
+    }
+}
@@ -0,0 +1,375 @@
+use crate::{Group, Point3d};
+use serde_json::{json, Value};
+use std::f64::consts::{PI, TAU};
+
+const SPHERE_SEGMENTS: u16 = 32;
+const SPHERE_RINGS: u16 = 16;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_SHORT: u32 = 5123;
+
+/// Build a binary glTF 2.0 file from the frames of an animation.
+///
+/// Each sphere becomes a node instancing a shared unit sphere mesh, with one material per grey value.
+/// The translation and scale of every node are sampled once per frame into an animation. The frames
+/// must all contain the same spheres in the same order, so there is an error if there are no frames
+/// or if the number of spheres changes.
+///
+/// The renderer looks along +z with y pointing down the image, whereas glTF looks along -z with y
+/// pointing up, so points are turned half a turn about the x axis on the way out.
+pub fn glb(frames: &[Group], frame_duration_seconds: f64) -> Result<Vec<u8>, String> {
+    let first_frame = frames
+        .first()
+        .ok_or("There is at least one frame to export.")?;
+    let sphere_count = first_frame.spheres.len();
+    if let Some(frame_index) = frames
+        .iter()
+        .position(|frame| frame.spheres.len() != sphere_count)
+    {
+        return Err(format!(
+            "Frame {frame_index} has {} spheres, but the first frame has {sphere_count}.",
+            frames[frame_index].spheres.len()
+        ));
+    }
+
+    let mut binary = vec![];
+    let mut buffer_views = vec![];
+    let mut accessors = vec![];
+
+    let (positions, indices) = unit_sphere();
+    let positions_view = push_view(
+        &mut binary,
+        &mut buffer_views,
+        &positions,
+        Some(ARRAY_BUFFER),
+    );
+    let position_accessor = accessors.len();
+    accessors.push(json!({
+        "bufferView": positions_view,
+        "componentType": FLOAT,
+        "count": positions.len() / 3,
+        "type": "VEC3",
+        "min": [-1.0, -1.0, -1.0],
+        "max": [1.0, 1.0, 1.0],
+    }));
+    let indices_bytes: Vec<u8> = indices
+        .iter()
+        .flat_map(|index| index.to_le_bytes())
+        .collect();
+    let indices_view = push_bytes(
+        &mut binary,
+        &mut buffer_views,
+        &indices_bytes,
+        Some(ELEMENT_ARRAY_BUFFER),
+    );
+    let indices_accessor = accessors.len();
+    accessors.push(json!({
+        "bufferView": indices_view,
+        "componentType": UNSIGNED_SHORT,
+        "count": indices.len(),
+        "type": "SCALAR",
+    }));
+
+    // One material, and so one mesh, per distinct grey value
+    let mut grey_values: Vec<u8> = first_frame
+        .spheres
+        .iter()
+        .map(|sphere| sphere.grey_value)
+        .collect();
+    grey_values.sort();
+    grey_values.dedup();
+    let materials: Vec<Value> = grey_values
+        .iter()
+        .map(|grey_value| {
+            let linear = srgb_to_linear(*grey_value);
+            json!({
+                "name": format!("grey {grey_value}"),
+                "pbrMetallicRoughness": {
+                    "baseColorFactor": [linear, linear, linear, 1.0],
+                    "metallicFactor": 0.0,
+                    "roughnessFactor": 1.0,
+                },
+            })
+        })
+        .collect();
+    let meshes: Vec<Value> = grey_values
+        .iter()
+        .enumerate()
+        .map(|(material, grey_value)| {
+            json!({
+                "name": format!("sphere grey {grey_value}"),
+                "primitives": [{
+                    "attributes": {
+                        "POSITION": position_accessor,
+                        "NORMAL": position_accessor,
+                    },
+                    "indices": indices_accessor,
+                    "material": material,
+                }],
+            })
+        })
+        .collect();
+
+    let nodes: Vec<Value> = first_frame
+        .spheres
+        .iter()
+        .enumerate()
+        .map(|(sphere_index, sphere)| {
+            let mesh = grey_values
+                .binary_search(&sphere.grey_value)
+                .expect("Every grey value has a mesh.");
+            json!({
+                "name": sphere.label(sphere_index),
+                "mesh": mesh,
+                "translation": to_gltf(&sphere.centre),
+                "scale": [sphere.radius, sphere.radius, sphere.radius],
+                "extras": { "tags": sphere.tags },
+            })
+        })
+        .collect();
+
+    let times: Vec<f32> = (0..frames.len())
+        .map(|frame_index| (frame_index as f64 * frame_duration_seconds) as f32)
+        .collect();
+    let times_view = push_view(&mut binary, &mut buffer_views, &times, None);
+    let times_accessor = accessors.len();
+    accessors.push(json!({
+        "bufferView": times_view,
+        "componentType": FLOAT,
+        "count": times.len(),
+        "type": "SCALAR",
+        "min": [times[0]],
+        "max": [times[times.len() - 1]],
+    }));
+
+    let mut samplers = vec![];
+    let mut channels = vec![];
+    for sphere_index in 0..sphere_count {
+        let translations: Vec<f32> = frames
+            .iter()
+            .flat_map(|frame| to_gltf(&frame.spheres[sphere_index].centre))
+            .collect();
+        let scales: Vec<f32> = frames
+            .iter()
+            .flat_map(|frame| [frame.spheres[sphere_index].radius as f32; 3])
+            .collect();
+        for (path, values) in [("translation", translations), ("scale", scales)] {
+            let view = push_view(&mut binary, &mut buffer_views, &values, None);
+            let accessor = accessors.len();
+            accessors.push(json!({
+                "bufferView": view,
+                "componentType": FLOAT,
+                "count": frames.len(),
+                "type": "VEC3",
+            }));
+            channels.push(json!({
+                "sampler": samplers.len(),
+                "target": { "node": sphere_index, "path": path },
+            }));
+            samplers.push(json!({
+                "input": times_accessor,
+                "output": accessor,
+                "interpolation": "LINEAR",
+            }));
+        }
+    }
+
+    while !binary.len().is_multiple_of(4) {
+        binary.push(0);
+    }
+
+    let document = json!({
+        "asset": { "version": "2.0", "generator": "grey-sphere-explanatory-animations" },
+        "scene": 0,
+        "scenes": [{ "nodes": (0..sphere_count).collect::<Vec<usize>>() }],
+        "nodes": nodes,
+        "meshes": meshes,
+        "materials": materials,
+        "animations": [{ "name": "animation", "samplers": samplers, "channels": channels }],
+        "accessors": accessors,
+        "bufferViews": buffer_views,
+        "buffers": [{ "byteLength": binary.len() }],
+    });
+
+    let mut json_chunk = serde_json::to_vec(&document).expect("The document has string keys.");
+    while !json_chunk.len().is_multiple_of(4) {
+        json_chunk.push(b' ');
+    }
+
+    let total_length = 12 + 8 + json_chunk.len() + 8 + binary.len();
+    let mut file = Vec::with_capacity(total_length);
+    file.extend(b"glTF");
+    file.extend(2u32.to_le_bytes());
+    file.extend((total_length as u32).to_le_bytes());
+    file.extend((json_chunk.len() as u32).to_le_bytes());
+    file.extend(b"JSON");
+    file.extend(json_chunk);
+    file.extend((binary.len() as u32).to_le_bytes());
+    file.extend(b"BIN\0");
+    file.extend(binary);
+    Ok(file)
+}
+
+// Positions of a unit sphere centred on the origin, which double as its normals, and its triangles.
+fn unit_sphere() -> (Vec<f32>, Vec<u16>) {
+    let mut positions = vec![];
+    for ring in 0..=SPHERE_RINGS {
+        let polar = PI * ring as f64 / SPHERE_RINGS as f64;
+        for segment in 0..=SPHERE_SEGMENTS {
+            let azimuth = TAU * segment as f64 / SPHERE_SEGMENTS as f64;
+            positions.push((polar.sin() * azimuth.cos()) as f32);
+            positions.push(polar.cos() as f32);
+            positions.push((polar.sin() * azimuth.sin()) as f32);
+        }
+    }
+
+    let mut indices = vec![];
+    let row_length = SPHERE_SEGMENTS + 1;
+    for ring in 0..SPHERE_RINGS {
+        for segment in 0..SPHERE_SEGMENTS {
+            let upper = ring * row_length + segment;
+            let lower = upper + row_length;
+            indices.extend([upper, upper + 1, lower]);
+            indices.extend([upper + 1, lower + 1, lower]);
+        }
+    }
+    (positions, indices)
+}
+
+fn to_gltf(point: &Point3d) -> [f32; 3] {
+    [point.x as f32, -point.y as f32, -point.z as f32]
+}
+
+fn srgb_to_linear(grey_value: u8) -> f64 {
+    let value = grey_value as f64 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn push_view(
+    binary: &mut Vec<u8>,
+    buffer_views: &mut Vec<Value>,
+    values: &[f32],
+    target: Option<u32>,
+) -> usize {
+    let bytes: Vec<u8> = values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+    push_bytes(binary, buffer_views, &bytes, target)
+}
+
+fn push_bytes(
+    binary: &mut Vec<u8>,
+    buffer_views: &mut Vec<Value>,
+    bytes: &[u8],
+    target: Option<u32>,
+) -> usize {
+    // Keep every view aligned to 4 bytes so that float accessors are correctly aligned
+    while !binary.len().is_multiple_of(4) {
+        binary.push(0);
+    }
+    let mut view = json!({
+        "buffer": 0,
+        "byteOffset": binary.len(),
+        "byteLength": bytes.len(),
+    });
+    if let Some(target) = target {
+        view["target"] = json!(target);
+    }
+    binary.extend(bytes);
+    buffer_views.push(view);
+    buffer_views.len() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sphere;
+
+    fn sphere(x: f64, radius: f64, grey_value: u8) -> Sphere {
+        Sphere {
+            centre: Point3d { x, y: 0.5, z: 0.0 },
+            radius,
+            grey_value,
+            name: None,
+            tags: Default::default(),
+        }
+    }
+
+    fn chunk(file: &[u8], start: usize) -> (&[u8], &[u8]) {
+        let length = u32::from_le_bytes(file[start..start + 4].try_into().unwrap()) as usize;
+        (
+            &file[start + 4..start + 8],
+            &file[start + 8..start + 8 + length],
+        )
+    }
+
+    #[test]
+    fn the_export_describes_every_sphere_and_frame() {
+        let frames = [
+            Group::new(vec![sphere(-1.0, 1.0, 0), sphere(1.0, 0.5, 200)]),
+            Group::new(vec![sphere(-2.0, 1.0, 0), sphere(2.0, 0.25, 200)]),
+            Group::new(vec![sphere(-3.0, 1.0, 0), sphere(3.0, 0.125, 200)]),
+        ];
+        let file = glb(&frames, 0.5).unwrap();
+        assert_eq!(&file[..4], b"glTF");
+        assert_eq!(
+            u32::from_le_bytes(file[8..12].try_into().unwrap()) as usize,
+            file.len()
+        );
+
+        let (json_type, json_chunk) = chunk(&file, 12);
+        assert_eq!(json_type, b"JSON");
+        let (binary_type, binary) = chunk(&file, 20 + json_chunk.len());
+        assert_eq!(binary_type, b"BIN\0");
+        let document: Value = serde_json::from_slice(json_chunk).unwrap();
+        assert_eq!(document["buffers"][0]["byteLength"], binary.len());
+        assert_eq!(document["nodes"].as_array().unwrap().len(), 2);
+        assert_eq!(document["materials"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            document["nodes"][1]["translation"],
+            json!([1.0, -0.5, -0.0])
+        );
+
+        // One translation and one scale channel per sphere, each with a keyframe per frame
+        let animation = &document["animations"][0];
+        assert_eq!(animation["channels"].as_array().unwrap().len(), 4);
+        let times =
+            &document["accessors"][animation["samplers"][0]["input"].as_u64().unwrap() as usize];
+        assert_eq!(times["count"], 3);
+        assert_eq!(times["max"], json!([1.0]));
+
+        // The scales of the second sphere, read back from the binary chunk
+        let scales =
+            &document["accessors"][animation["samplers"][3]["output"].as_u64().unwrap() as usize];
+        let view = &document["bufferViews"][scales["bufferView"].as_u64().unwrap() as usize];
+        let offset = view["byteOffset"].as_u64().unwrap() as usize;
+        let values: Vec<f32> = binary
+            [offset..offset + view["byteLength"].as_u64().unwrap() as usize]
+            .chunks(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert_eq!(
+            values,
+            [0.5, 0.5, 0.5, 0.25, 0.25, 0.25, 0.125, 0.125, 0.125]
+        );
+    }
+
+    #[test]
+    fn frames_with_different_numbers_of_spheres_are_an_error() {
+        let frames = [
+            Group::new(vec![sphere(-1.0, 1.0, 0), sphere(1.0, 0.5, 200)]),
+            Group::new(vec![sphere(-1.0, 1.0, 0)]),
+        ];
+        assert_eq!(
+            glb(&frames, 0.5).err(),
+            Some("Frame 1 has 1 spheres, but the first frame has 2.".to_string())
+        );
+        assert!(glb(&[], 0.5).is_err());
+    }
+}
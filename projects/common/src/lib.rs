@@ -0,0 +1,655 @@
+#[cfg(feature = "checks")]
+pub mod animation_checks;
+pub mod apng;
+pub mod clock;
+pub mod contrast;
+pub mod easing;
+pub mod encoding;
+pub mod explode;
+pub mod frame_options;
+pub mod gltf;
+pub mod morph;
+pub mod motion_blur;
+pub mod palette;
+pub mod parameters;
+pub mod path;
+pub mod phase;
+pub mod random_ball;
+pub mod render_options;
+pub mod scene;
+pub mod scene_graph;
+#[cfg(feature = "script")]
+pub mod script;
+pub mod sprite_sheet;
+pub mod timeline;
+pub mod validation;
+
+use apng::Apng;
+use base64::prelude::*;
+use contrast::contrast_scheme;
+use explode::{Explode, ExplodeOptions};
+use morph::Morph;
+use palette::SchemeName;
+use parameters::AnimationParameters;
+use path::Path;
+use phase::Phase;
+use random_ball::RandomBall;
+use render_options::RenderOptions;
+use scene::Scene;
+#[cfg(feature = "script")]
+use script::Script;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "script")]
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::BufWriter;
+use std::ops::{Add, Mul, Sub};
+use validation::ContinuityReport;
+pub const WIDTH: u32 = 1024;
+pub const HEIGHT: u32 = 1024;
+pub const DEFAULT_FRAMES_PER_SECOND: f64 = 50.0;
+
+/// Render every frame of an animation lasting `duration_seconds` as `options` describe, taking the
+/// group at each fraction of the way through from `frame`, and finishing on `finished_frame` if the
+/// animation plays once.
+pub fn render<P: AnimationParameters>(
+    options: &RenderOptions<P>,
+    duration_seconds: f64,
+    phases: &[Phase],
+    frame: impl Fn(f64) -> Group,
+    finished_frame: &Group,
+) -> Apng {
+    render_with_progress(
+        options,
+        duration_seconds,
+        phases,
+        frame,
+        finished_frame,
+        |_, _| (),
+    )
+}
+
+/// Render as `render` does, calling `progress` with the index of each frame and the number of frames
+/// before the frame is rendered.
+pub fn render_with_progress<P: AnimationParameters>(
+    options: &RenderOptions<P>,
+    duration_seconds: f64,
+    phases: &[Phase],
+    frame: impl Fn(f64) -> Group,
+    finished_frame: &Group,
+    progress: impl Fn(u32, u32),
+) -> Apng {
+    let clock = options.clock(duration_seconds);
+    let delays = options.delays(&clock, phases);
+    let mut apng = Apng::new(WIDTH, HEIGHT, clock::DELAY_DENOMINATOR);
+    apng.set_loop_count(options.loop_count());
+    let frame_count = clock.frame_count();
+    for frame_index in 0..frame_count {
+        progress(frame_index, frame_count);
+        let animation_fraction = clock.animation_fraction(frame_index);
+        let pixels = options.frame_pixels(&clock, animation_fraction, |animation_fraction| {
+            frame_pixels(WIDTH, HEIGHT, &frame(animation_fraction).spheres)
+        });
+        apng.add_frame(pixels, delays[frame_index as usize]);
+    }
+    if options.play_once {
+        apng.add_frame(
+            frame_pixels(WIDTH, HEIGHT, &finished_frame.spheres),
+            delays[0],
+        );
+    }
+    apng
+}
+
+// Animations built from JSON or a script have no parameters of their own, and are rendered with the
+// default options
+fn render_without_options(
+    duration_seconds: f64,
+    frame: impl Fn(f64) -> Group,
+    finished_frame: &Group,
+) -> String {
+    let apng = render(
+        &RenderOptions::<()>::default(),
+        duration_seconds,
+        &[],
+        frame,
+        finished_frame,
+    );
+    png_data_url(&apng.encode_with(&apng.optimise()))
+}
+
+/// Take apart the assembled group given as JSON, in the form `scene_json` gives, and put it back
+/// together as the `ExplodeOptions` given as JSON describe, rendered in the same way as the built-in
+/// animation at the default frame rate.
+pub fn explode_data_url(group_json: &str, options_json: &str) -> Result<String, String> {
+    let group = Group::from_json(group_json).map_err(|error| error.to_string())?;
+    let explode = Explode::new(group, ExplodeOptions::from_json(options_json)?)?;
+    Ok(render_without_options(
+        explode.duration_seconds(),
+        |animation_fraction| explode.frame(animation_fraction),
+        &explode.frame(0.0),
+    ))
+}
+
+/// Check the movement of every sphere taken apart by `explode_data_url` for jumps in value or slope,
+/// and check that it loops back to the assembled group, as JSON.
+pub fn explode_continuity_report(group_json: &str, options_json: &str) -> Result<String, String> {
+    let group = Group::from_json(group_json).map_err(|error| error.to_string())?;
+    let explode = Explode::new(group, ExplodeOptions::from_json(options_json)?)?;
+    let report = ContinuityReport::new(explode.tracks(), |animation_fraction| {
+        explode.frame(animation_fraction)
+    });
+    Ok(serde_json::to_string_pretty(&report).expect("The report contains no maps."))
+}
+
+/// Morph between two groups given as JSON, in the form `scene_json` gives, such as the assembled
+/// beachball and the assembled starball from their own modules. The animation holds the first group,
+/// morphs into the second, holds it and morphs back, rendered in the same way as the built-in
+/// animation at the default frame rate. See `morph::Morph`.
+pub fn morph_data_url(from_json: &str, to_json: &str) -> Result<String, String> {
+    let from = Group::from_json(from_json).map_err(|error| error.to_string())?;
+    let to = Group::from_json(to_json).map_err(|error| error.to_string())?;
+    let morph = Morph::new(&from, &to);
+    Ok(render_without_options(
+        morph::DURATION_SECONDS,
+        |animation_fraction| morph.frame(animation_fraction),
+        &morph.frame(0.0),
+    ))
+}
+
+/// A novelty ball built at random from `seed`, coming apart and back together, rendered in the same
+/// way as the built-in animation at the default frame rate. See `random_ball::RandomBall`.
+pub fn random_ball_data_url(seed: u32) -> String {
+    let ball = RandomBall::new(seed);
+    render_without_options(
+        ball.duration_seconds(),
+        |animation_fraction| ball.frame(animation_fraction),
+        &ball.frame(0.0),
+    )
+}
+
+/// The random ball built from `seed` at `animation_fraction` as JSON, in the same form as
+/// `scene_json`, for keeping as a regression fixture.
+pub fn random_ball_json(seed: u32, animation_fraction: f64) -> String {
+    RandomBall::new(seed).frame(animation_fraction).to_json()
+}
+
+/// Load an animation described in the JSON scene format and render it in the same way as the
+/// built-in animation, at the default frame rate. The frames take their greys from `scheme`, which
+/// is the animation's default scheme.
+pub fn scene_animation_data_url(json: &str, scheme: SchemeName) -> Result<String, String> {
+    let scene = Scene::from_json(json)?;
+    Ok(render_without_options(
+        scene.duration_seconds,
+        |animation_fraction| scene.frame(animation_fraction, scheme),
+        &scene.frame(0.0, SchemeName::Standard),
+    ))
+}
+
+/// Run an animation written as a Rhai script and render it in the same way as the built-in animation,
+/// at the default frame rate. See `script::Script` for what a script can use.
+#[cfg(feature = "script")]
+pub fn script_animation_data_url(source: &str) -> Result<String, String> {
+    let script = Script::new(source)?;
+    let finished_frame = script.frame(0.0)?;
+    // Rendering takes a frame for every fraction, so keep the first error and report it at the end
+    let error = RefCell::new(None);
+    let data_url = render_without_options(
+        script.duration_seconds,
+        |animation_fraction| {
+            script.frame(animation_fraction).unwrap_or_else(|message| {
+                error.borrow_mut().get_or_insert(message);
+                Group::new(vec![])
+            })
+        },
+        &finished_frame,
+    );
+    match error.into_inner() {
+        Some(message) => Err(message),
+        None => Ok(data_url),
+    }
+}
+
+/// Render a scene exported by `scene_json` as a still PNG.
+pub fn scene_image_data_url(json: &str) -> Result<String, String> {
+    let scene = Group::from_json(json).map_err(|error| error.to_string())?;
+    Ok(png_data_url(&grayscale_png(
+        WIDTH,
+        HEIGHT,
+        &frame_pixels(WIDTH, HEIGHT, &scene.spheres),
+    )))
+}
+
+/// The same as `contrast_scheme_json` for an animation described in the JSON scene format, which is
+/// assembled in its first frame.
+pub fn scene_contrast_scheme_json(json: &str) -> Result<String, String> {
+    let scene = Scene::from_json(json)?;
+    Ok(
+        serde_json::to_string(&contrast_scheme(&scene.frame(0.0, SchemeName::Standard)))
+            .expect("A scheme contains no maps."),
+    )
+}
+
+/// Sample a path described in JSON at `samples` evenly spaced steps of progress from its start to its
+/// end inclusive, giving the points as JSON, for previewing the path a sphere would follow.
+pub fn sample_path(json: &str, samples: u32) -> Result<String, String> {
+    let path: Path = serde_json::from_str(json).map_err(|error| error.to_string())?;
+    let intervals = samples.saturating_sub(1).max(1) as f64;
+    let points: Vec<Point3d> = (0..samples)
+        .map(|sample| path.point(sample as f64 / intervals))
+        .collect();
+    Ok(serde_json::to_string(&points).expect("A point contains no maps."))
+}
+
+/// Sample the named easing at `samples` evenly spaced points from 0.0 to 1.0 inclusive, for plotting
+/// its curve.
+pub fn easing_curve(name: &str, samples: u32) -> Result<Vec<f64>, String> {
+    let easing = easing::by_name(name).ok_or(format!("There is no easing called \"{name}\"."))?;
+    let intervals = samples.saturating_sub(1).max(1) as f64;
+    Ok((0..samples)
+        .map(|sample| easing.ease(sample as f64 / intervals))
+        .collect())
+}
+
+pub fn png_data_url(file: &[u8]) -> String {
+    let base64_data = BASE64_STANDARD.encode(file);
+    format!("data:image/png;base64,{}", base64_data)
+}
+
+pub fn grayscale_png(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let mut file = vec![];
+    {
+        // Start a block so that the PNG file will be closed after the image has been written
+        let w = &mut BufWriter::new(&mut file);
+        let mut encoder = png::Encoder::new(w, width, height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(pixels).unwrap();
+    }
+    file
+}
+
+pub fn frame_pixels(width: u32, height: u32, spheres: &[Sphere]) -> Vec<u8> {
+    let mut data = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            let normalised_x: f64 = x as f64 / width as f64 * 2.0 - 1.0;
+            let normalised_y: f64 = y as f64 / height as f64 * 2.0 - 1.0;
+            let result = spheres
+                .iter()
+                .filter_map(sphere_with_intersection_distance(
+                    normalised_x,
+                    normalised_y,
+                ))
+                .reduce(|current, next| {
+                    if next.distance < current.distance {
+                        next
+                    } else {
+                        current
+                    }
+                });
+            let grey = match result {
+                Some(intersection) => intersection.sphere.grey_value,
+                _ => 0,
+            };
+            data.push(grey)
+        }
+    }
+    data
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct Group {
+    pub spheres: Vec<Sphere>,
+}
+
+impl Group {
+    pub fn new(spheres: Vec<Sphere>) -> Self {
+        Self { spheres }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("A group contains no maps.")
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn rotate(&self, angle: f64, axis: &Point3d) -> Option<Self> {
+        match axis {
+            x if *x == Point3d::origin() => None,
+            _ => Some(Self {
+                spheres: self
+                    .spheres
+                    .iter()
+                    .filter_map(|sphere| sphere.rotate(angle, axis))
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl Add for Group {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut spheres = self.spheres;
+        spheres.extend(rhs.spheres);
+        Self { spheres }
+    }
+}
+
+impl Add<Point3d> for Group {
+    type Output = Self;
+
+    fn add(self, rhs: Point3d) -> Self::Output {
+        Self {
+            spheres: self
+                .spheres
+                .iter()
+                .map(|sphere| sphere.clone() + rhs.clone())
+                .collect(),
+        }
+    }
+}
+
+impl Sub<Point3d> for Group {
+    type Output = Self;
+
+    fn sub(self, rhs: Point3d) -> Self::Output {
+        Self {
+            spheres: self
+                .spheres
+                .iter()
+                .map(|sphere| sphere.clone() - rhs.clone())
+                .collect(),
+        }
+    }
+}
+
+impl Mul<f64> for Group {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self {
+            spheres: self
+                .spheres
+                .iter()
+                .map(|sphere| sphere.clone() * rhs)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct Sphere {
+    pub centre: Point3d,
+    pub radius: f64,
+    pub grey_value: u8,
+    /// What diagnostics call the sphere, such as "star sphere 3".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Labels shared by related spheres, such as "dodecahedron", so they can be picked out together.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+}
+
+impl Sphere {
+    pub fn rotate(&self, angle: f64, axis: &Point3d) -> Option<Self> {
+        Some(Self {
+            centre: self.centre.rotate(angle, axis)?,
+            ..self.clone()
+        })
+    }
+
+    /// The name of the sphere, or its position in its group if it has no name.
+    pub fn label(&self, index: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("sphere {index}"))
+    }
+}
+
+pub fn tag_set(tags: &[&str]) -> BTreeSet<String> {
+    tags.iter().map(|tag| tag.to_string()).collect()
+}
+
+impl fmt::Display for Sphere {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}, {}, {}],",
+            self.centre, self.radius, self.grey_value
+        )
+    }
+}
+
+impl Mul<f64> for Sphere {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self {
+            centre: self.centre * rhs,
+            radius: self.radius * rhs,
+            ..self
+        }
+    }
+}
+
+impl Add<Point3d> for Sphere {
+    type Output = Self;
+
+    fn add(self, rhs: Point3d) -> Self::Output {
+        Self {
+            centre: self.centre + rhs,
+            ..self
+        }
+    }
+}
+
+impl Sub<Point3d> for Sphere {
+    type Output = Self;
+
+    fn sub(self, rhs: Point3d) -> Self::Output {
+        Self {
+            centre: self.centre - rhs,
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct Point3d {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Point3d {
+    pub fn normalise(&self) -> Option<Self> {
+        let distance = Self::distance(self, &Self::origin());
+        if distance == 0.0 {
+            return None;
+        }
+        let x = self.x / distance;
+        let y = self.y / distance;
+        let z = self.z / distance;
+        Some(Self { x, y, z })
+    }
+
+    pub fn distance(a: &Self, b: &Self) -> f64 {
+        ((a.x - b.x).powf(2.0) + (a.y - b.y).powf(2.0) + (a.z - b.z).powf(2.0)).powf(0.5)
+    }
+
+    pub fn origin() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    // Based on https://en.wikipedia.org/wiki/Rotation_matrix#Rotation_matrix_from_axis_and_angle
+    pub fn rotate(&self, angle: f64, axis: &Self) -> Option<Self> {
+        let u = axis.normalise()?;
+        let s = angle.sin();
+        let c = angle.cos();
+        let d = 1.0 - c;
+        Some(Self {
+            x: self.x * (c + u.x.powf(2.0) * d)
+                + self.y * (u.x * u.y * d - u.z * s)
+                + self.z * (u.x * u.z * d + u.y * s),
+            y: self.x * (u.y * u.x * d + u.z * s)
+                + self.y * (c + u.y.powf(2.0) * d)
+                + self.z * (u.y * u.z * d - u.x * s),
+            z: self.x * (u.z * u.x * d - u.y * s)
+                + self.y * (u.z * u.y * d + u.x * s)
+                + self.z * (c + u.z.powf(2.0) * d),
+        })
+    }
+
+    pub fn x_axis() -> Self {
+        Self {
+            x: 1.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    pub fn y_axis() -> Self {
+        Self {
+            x: 0.0,
+            y: 1.0,
+            z: 0.0,
+        }
+    }
+    pub fn z_axis() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        }
+    }
+}
+
+impl Add for Point3d {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+        }
+    }
+}
+
+impl Sub for Point3d {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + Self {
+            x: -rhs.x,
+            y: -rhs.y,
+            z: -rhs.z,
+        }
+    }
+}
+
+impl Mul<f64> for Point3d {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+        }
+    }
+}
+
+impl fmt::Display for Point3d {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, {}, {}", self.x, self.y, self.z)
+    }
+}
+
+struct Point2d {
+    x: f64,
+    y: f64,
+}
+
+impl Point2d {
+    fn distance(a: Self, b: Self) -> f64 {
+        ((a.x - b.x).powf(2.0) + (a.y - b.y).powf(2.0)).powf(0.5)
+    }
+}
+
+struct Intersection<'a> {
+    sphere: &'a Sphere,
+    distance: f64,
+}
+
+fn sphere_with_intersection_distance<'a>(
+    x: f64,
+    y: f64,
+) -> impl FnMut(&'a Sphere) -> Option<Intersection<'a>> {
+    move |sphere| {
+        let distance = Point2d::distance(
+            Point2d { x, y },
+            Point2d {
+                x: sphere.centre.x,
+                y: sphere.centre.y,
+            },
+        );
+        let radius = sphere.radius;
+        if distance < radius {
+            Some(Intersection {
+                sphere,
+                distance: 1000.0 + sphere.centre.z
+                    - (radius.powf(2.0) - distance.powf(2.0)).powf(0.5)
+                    - 1000.0,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_group_reads_back_from_its_json_unchanged() {
+        let group = Group::new(vec![
+            Sphere {
+                centre: Point3d {
+                    x: f64::MAX,
+                    y: f64::MIN,
+                    z: -1e-300,
+                },
+                radius: f64::MIN_POSITIVE,
+                grey_value: u8::MAX,
+                name: Some("star sphere \"3\"".to_string()),
+                tags: tag_set(&["star", "point"]),
+            },
+            Sphere {
+                centre: Point3d {
+                    x: f64::EPSILON,
+                    y: 5e-324,
+                    z: 0.1 + 0.2,
+                },
+                radius: 1.0 / 3.0,
+                grey_value: 0,
+                name: None,
+                tags: BTreeSet::new(),
+            },
+        ]);
+        let read_back = Group::from_json(&group.to_json()).expect("The group is valid JSON.");
+        assert!(read_back == group);
+    }
+}
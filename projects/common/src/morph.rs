@@ -0,0 +1,206 @@
+use crate::timeline::Timeline;
+use crate::{Group, Point3d, Sphere};
+
+pub const DURATION_SECONDS: f64 = 8.0;
+
+// Hold the first group, morph into the second, hold it, and morph back
+const MORPH_KEYFRAMES: [(f64, [f64; 1]); 5] = [
+    (0.0, [0.0]),
+    (0.15, [0.0]),
+    (0.5, [1.0]),
+    (0.65, [1.0]),
+    (1.0, [0.0]),
+];
+
+/// A continuous change from one group of spheres into another.
+///
+/// Each sphere of the first group is matched with a sphere of the second, choosing the matching that
+/// moves the spheres the shortest distance in total, and each pair moves, grows or shrinks and changes
+/// grey together. When one group has more spheres than the other, the spheres left over shrink away
+/// to nothing where they are, or grow from nothing where they will be.
+pub struct Morph {
+    pairs: Vec<(Sphere, Sphere)>,
+}
+
+impl Morph {
+    pub fn new(from: &Group, to: &Group) -> Self {
+        let size = from.spheres.len().max(to.spheres.len());
+        // A sphere missing from either group stands in for the sphere left over, so it costs nothing
+        let cost: Vec<Vec<f64>> = (0..size)
+            .map(|row| {
+                (0..size)
+                    .map(
+                        |column| match (from.spheres.get(row), to.spheres.get(column)) {
+                            (Some(a), Some(b)) => Point3d::distance(&a.centre, &b.centre),
+                            _ => 0.0,
+                        },
+                    )
+                    .collect()
+            })
+            .collect();
+        let vanished = |sphere: &Sphere| Sphere {
+            radius: 0.0,
+            ..sphere.clone()
+        };
+        let pairs = assignment(&cost)
+            .into_iter()
+            .enumerate()
+            .filter_map(
+                |(row, column)| match (from.spheres.get(row), to.spheres.get(column)) {
+                    (Some(a), Some(b)) => Some((a.clone(), b.clone())),
+                    (Some(a), None) => Some((a.clone(), vanished(a))),
+                    (None, Some(b)) => Some((vanished(b), b.clone())),
+                    (None, None) => None,
+                },
+            )
+            .collect();
+        Self { pairs }
+    }
+
+    /// The spheres `progress` of the way from the first group, at 0.0, to the second, at 1.0. Each
+    /// sphere keeps the name and tags of the first group until halfway, and then takes those of the
+    /// second.
+    pub fn between(&self, progress: f64) -> Group {
+        let mix = |a: f64, b: f64| a + (b - a) * progress;
+        Group::new(
+            self.pairs
+                .iter()
+                .map(|(a, b)| {
+                    let labelled = match progress < 0.5 {
+                        true => a,
+                        false => b,
+                    };
+                    Sphere {
+                        centre: a.centre.clone() + (b.centre.clone() - a.centre.clone()) * progress,
+                        radius: mix(a.radius, b.radius),
+                        grey_value: mix(a.grey_value as f64, b.grey_value as f64).round() as u8,
+                        ..labelled.clone()
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// The morph as a looping animation, holding each group in turn.
+    pub fn frame(&self, animation_fraction: f64) -> Group {
+        self.between(morph_timeline().sample(animation_fraction))
+    }
+}
+
+fn morph_timeline() -> Timeline {
+    Timeline::from_table(&MORPH_KEYFRAMES, 0)
+}
+
+// The column assigned to each row of the square `cost` matrix so that the total cost is least, by the
+// Hungarian algorithm. Each row is added in turn, and the assignment so far is rearranged along the
+// cheapest augmenting path, with a potential for every row and column keeping the reduced costs
+// non-negative. Rows and columns are counted from 1 here, with column 0 standing for the row being
+// added.
+fn assignment(cost: &[Vec<f64>]) -> Vec<usize> {
+    let size = cost.len();
+    let mut row_potential = vec![0.0; size + 1];
+    let mut column_potential = vec![0.0; size + 1];
+    // The row assigned to each column, or 0 for none
+    let mut assigned_row = vec![0; size + 1];
+    // The previous column on the cheapest path found to each column
+    let mut previous = vec![0; size + 1];
+    for row in 1..=size {
+        assigned_row[0] = row;
+        let mut column = 0;
+        let mut least = vec![f64::INFINITY; size + 1];
+        let mut visited = vec![false; size + 1];
+        while assigned_row[column] != 0 {
+            visited[column] = true;
+            let current_row = assigned_row[column];
+            let mut delta = f64::INFINITY;
+            let mut next_column = 0;
+            for candidate in 1..=size {
+                if visited[candidate] {
+                    continue;
+                }
+                let reduced = cost[current_row - 1][candidate - 1]
+                    - row_potential[current_row]
+                    - column_potential[candidate];
+                if reduced < least[candidate] {
+                    least[candidate] = reduced;
+                    previous[candidate] = column;
+                }
+                if least[candidate] < delta {
+                    delta = least[candidate];
+                    next_column = candidate;
+                }
+            }
+            for candidate in 0..=size {
+                if visited[candidate] {
+                    row_potential[assigned_row[candidate]] += delta;
+                    column_potential[candidate] -= delta;
+                } else {
+                    least[candidate] -= delta;
+                }
+            }
+            column = next_column;
+        }
+        while column != 0 {
+            let previous_column = previous[column];
+            assigned_row[column] = assigned_row[previous_column];
+            column = previous_column;
+        }
+    }
+    let mut assigned_column = vec![0; size];
+    for (column, row) in assigned_row.into_iter().enumerate().skip(1) {
+        assigned_column[row - 1] = column - 1;
+    }
+    assigned_column
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sphere(x: f64, radius: f64, grey_value: u8) -> Sphere {
+        Sphere {
+            centre: Point3d { x, y: 0.0, z: 0.0 },
+            radius,
+            grey_value,
+            name: None,
+            tags: Default::default(),
+        }
+    }
+
+    #[test]
+    fn the_assignment_costs_least_in_total() {
+        // Taking the cheapest entry of each row in turn would cost 1 + 5 + 9 = 15, but the least
+        // total is 2 + 7 + 3 = 12
+        let cost = vec![
+            vec![1.0, 2.0, 9.0],
+            vec![4.0, 5.0, 7.0],
+            vec![3.0, 8.0, 9.0],
+        ];
+        assert_eq!(assignment(&cost), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn spheres_move_to_the_nearest_place_whatever_their_order() {
+        let from = Group::new(vec![sphere(-1.0, 1.0, 0), sphere(1.0, 1.0, 200)]);
+        let to = Group::new(vec![sphere(1.5, 0.5, 100), sphere(-1.5, 0.5, 50)]);
+        let halfway = Morph::new(&from, &to).between(0.5);
+        assert_eq!(halfway.spheres[0].centre.x, -1.25);
+        assert_eq!(halfway.spheres[0].radius, 0.75);
+        assert_eq!(halfway.spheres[0].grey_value, 25);
+        assert_eq!(halfway.spheres[1].centre.x, 1.25);
+        assert_eq!(halfway.spheres[1].grey_value, 150);
+    }
+
+    #[test]
+    fn spheres_left_over_shrink_away_or_grow_from_nothing() {
+        let one = Group::new(vec![sphere(0.0, 1.0, 0)]);
+        let two = Group::new(vec![sphere(0.1, 1.0, 0), sphere(3.0, 1.0, 0)]);
+        let growing = Morph::new(&one, &two);
+        assert_eq!(growing.between(0.0).spheres[1].radius, 0.0);
+        assert_eq!(growing.between(0.5).spheres[1].radius, 0.5);
+        assert_eq!(growing.between(1.0).spheres[1].centre.x, 3.0);
+        let shrinking = Morph::new(&two, &one);
+        assert_eq!(shrinking.between(1.0).spheres[1].radius, 0.0);
+        assert_eq!(shrinking.between(1.0).spheres[1].centre.x, 3.0);
+    }
+}
@@ -0,0 +1,94 @@
+use serde::Deserialize;
+
+// The most samples, each of which renders the whole frame again, so that blurring at most multiplies
+// the time taken to render an animation by 64
+const MAX_SAMPLES: u32 = 64;
+
+/// Blur moving spheres by averaging several renders taken while a virtual shutter is open.
+///
+/// The shutter angle is the fraction of each frame's interval that the shutter is open, in degrees
+/// as on a film camera, so 180 blurs over the first half of the interval and 360 over all of it. The
+/// samples are spread evenly across the open interval.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MotionBlur {
+    pub shutter_angle: f64,
+    pub samples: u32,
+}
+
+impl MotionBlur {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.shutter_angle > 0.0 && self.shutter_angle <= 360.0) {
+            return Err("The shutter angle is more than 0 and at most 360 degrees.".to_string());
+        }
+        if !(1..=MAX_SAMPLES).contains(&self.samples) {
+            return Err(format!(
+                "The number of motion blur samples is from 1 to {MAX_SAMPLES}."
+            ));
+        }
+        Ok(())
+    }
+
+    /// Average the pixels rendered by `render` at each sample within the frame that starts at
+    /// `animation_fraction` and lasts `frame_fraction` of the animation.
+    pub fn render(
+        &self,
+        animation_fraction: f64,
+        frame_fraction: f64,
+        render: impl Fn(f64) -> Vec<u8>,
+    ) -> Vec<u8> {
+        let open_fraction = frame_fraction * self.shutter_angle / 360.0;
+        let mut totals: Vec<u32> = vec![];
+        for sample in 0..self.samples {
+            let pixels = render(
+                animation_fraction + open_fraction * (sample as f64 + 0.5) / self.samples as f64,
+            );
+            if totals.is_empty() {
+                totals = vec![0; pixels.len()];
+            }
+            for (total, pixel) in totals.iter_mut().zip(pixels) {
+                *total += pixel as u32;
+            }
+        }
+        totals
+            .iter()
+            .map(|total| ((*total as f64 / self.samples as f64).round()) as u8)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn motion_blur(shutter_angle: f64, samples: u32) -> MotionBlur {
+        MotionBlur {
+            shutter_angle,
+            samples,
+        }
+    }
+
+    #[test]
+    fn the_samples_are_from_1_to_the_maximum() {
+        assert!(motion_blur(180.0, 1).validate().is_ok());
+        assert!(motion_blur(180.0, MAX_SAMPLES).validate().is_ok());
+        for samples in [0, MAX_SAMPLES + 1, u32::MAX] {
+            assert_eq!(
+                motion_blur(180.0, samples).validate(),
+                Err("The number of motion blur samples is from 1 to 64.".to_string())
+            );
+        }
+        assert!(motion_blur(0.0, 8).validate().is_err());
+        assert!(motion_blur(361.0, 8).validate().is_err());
+    }
+
+    #[test]
+    fn the_samples_are_averaged_across_the_open_shutter() {
+        // Each sample renders its own time as a grey value, so the average is the middle of the
+        // interval the shutter is open for
+        let pixels = motion_blur(180.0, 4).render(0.0, 0.8, |animation_fraction| {
+            vec![(animation_fraction * 100.0).round() as u8; 2]
+        });
+        assert_eq!(pixels, vec![20, 20]);
+    }
+}
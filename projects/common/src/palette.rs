@@ -0,0 +1,94 @@
+use crate::parameters::AnimationParameters;
+use crate::{Group, Sphere};
+use serde::{Deserialize, Serialize};
+
+/// The grey of a sphere before a colour scheme gives it one.
+pub const UNCOLOURED: u8 = 0;
+
+/// The colour schemes that every animation has.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemeName {
+    /// The finished look of the ball.
+    Standard,
+    /// Different greys for spheres that look alike in the standard scheme, to show how the ball is
+    /// made.
+    Explanatory,
+}
+
+/// A colour scheme chosen at runtime, as `"standard"`, `"explanatory"` or a custom scheme such as
+/// `{"rules": [["star-point", 30], ["star sphere 4", 255]]}`.
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum SchemeChoice {
+    Named(SchemeName),
+    Custom(Scheme),
+}
+
+/// Rules giving a grey value to the spheres with a tag or a name.
+///
+/// The rules apply in order, so a later rule overrides an earlier one for the spheres they both
+/// match, and spheres that no rule matches keep their grey. Custom schemes are laid over the
+/// standard scheme, so they only need rules for the spheres they recolour.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Scheme {
+    pub rules: Vec<(String, u8)>,
+}
+
+impl Scheme {
+    pub fn new(rules: &[(&str, u8)]) -> Self {
+        Self {
+            rules: rules
+                .iter()
+                .map(|(key, grey_value)| (key.to_string(), *grey_value))
+                .collect(),
+        }
+    }
+
+    /// This scheme with the rules of `other` after its own, so that `other` takes precedence.
+    pub fn then(mut self, other: &Self) -> Self {
+        self.rules.extend(other.rules.iter().cloned());
+        self
+    }
+
+    /// The grey value of the last rule that matches a tag or the name of `sphere`.
+    pub fn grey_value(&self, sphere: &Sphere) -> Option<u8> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(key, _)| sphere.tags.contains(key) || sphere.name.as_ref() == Some(key))
+            .map(|(_, grey_value)| *grey_value)
+    }
+
+    pub fn colour(&self, group: Group) -> Group {
+        Group::new(
+            group
+                .spheres
+                .into_iter()
+                .map(|sphere| Sphere {
+                    grey_value: self.grey_value(&sphere).unwrap_or(sphere.grey_value),
+                    ..sphere
+                })
+                .collect(),
+        )
+    }
+}
+
+impl SchemeChoice {
+    /// The default scheme of the animation that `P` varies.
+    pub fn default_for<P: AnimationParameters>() -> Self {
+        Self::Named(P::DEFAULT_SCHEME)
+    }
+
+    /// The scheme with all of its rules, from the animation's own standard and explanatory rules for
+    /// the variant that `parameters` describe.
+    pub fn scheme<P: AnimationParameters>(&self, parameters: &P) -> Scheme {
+        let standard = parameters.standard_scheme();
+        match self {
+            Self::Named(SchemeName::Standard) => standard,
+            Self::Named(SchemeName::Explanatory) => standard.then(&parameters.explanatory_scheme()),
+            Self::Custom(custom) => standard.then(custom),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use crate::palette::{Scheme, SchemeName};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+/// The parameters that vary an animation, read from JSON with every field optional, and the colour
+/// schemes of the variant of the animation that they describe.
+pub trait AnimationParameters: Serialize + DeserializeOwned + Default {
+    /// Every parameter, each of which is a field of the same name.
+    const SCHEMA: &'static [ParameterSchema];
+    /// The colour scheme of the animation unless another is chosen.
+    const DEFAULT_SCHEME: SchemeName;
+
+    fn standard_scheme(&self) -> Scheme;
+
+    /// The rules laid over the standard scheme to tell apart spheres that look alike in it.
+    fn explanatory_scheme(&self) -> Scheme;
+}
+
+// Animations read from JSON or a script are described completely by their source, so there is
+// nothing to vary, and they keep the greys they give their spheres
+impl AnimationParameters for () {
+    const SCHEMA: &'static [ParameterSchema] = &[];
+    const DEFAULT_SCHEME: SchemeName = SchemeName::Standard;
+
+    fn standard_scheme(&self) -> Scheme {
+        Scheme::new(&[])
+    }
+
+    fn explanatory_scheme(&self) -> Scheme {
+        Scheme::new(&[])
+    }
+}
+
+/// A parameter that varies an animation, described so that a UI can build a control for it without
+/// knowing the animation.
+#[derive(Serialize)]
+pub struct ParameterSchema {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ParameterType {
+    /// A whole number, such as a count of spheres.
+    Integer,
+    /// Any number in the range, such as a size or an angle in radians.
+    Number,
+}
+
+// A parameter as it is exported, with its default taken from the animation's `Parameters::default`
+// and its type from the type of the field, so that neither is written twice
+#[derive(Serialize)]
+struct ParameterDescription {
+    #[serde(flatten)]
+    schema: &'static ParameterSchema,
+    #[serde(rename = "type")]
+    kind: ParameterType,
+    default: Value,
+}
+
+/// Every parameter of the animation with its default, as JSON.
+pub fn schema_json<P: AnimationParameters>() -> String {
+    let defaults = values(&P::default());
+    let descriptions: Vec<ParameterDescription> = P::SCHEMA
+        .iter()
+        .map(|schema| {
+            let default = defaults[schema.name].clone();
+            ParameterDescription {
+                schema,
+                kind: match default.is_u64() {
+                    true => ParameterType::Integer,
+                    false => ParameterType::Number,
+                },
+                default,
+            }
+        })
+        .collect();
+    serde_json::to_string_pretty(&descriptions).expect("A parameter contains no maps.")
+}
+
+/// Check that every parameter is within the range its schema gives.
+pub fn validate<P: AnimationParameters>(parameters: &P) -> Result<(), String> {
+    let values = values(parameters);
+    for schema in P::SCHEMA {
+        let value = values[schema.name]
+            .as_f64()
+            .expect("Every parameter in the schema is a number.");
+        if !(schema.min..=schema.max).contains(&value) {
+            return Err(format!(
+                "The {} parameter is from {} to {}.",
+                schema.name, schema.min, schema.max
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn values<P: AnimationParameters>(parameters: &P) -> Value {
+    serde_json::to_value(parameters).expect("Parameters contain no maps.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    struct Parameters {
+        count: u32,
+        size: f64,
+    }
+
+    impl Default for Parameters {
+        fn default() -> Self {
+            Self {
+                count: 3,
+                size: 0.5,
+            }
+        }
+    }
+
+    impl AnimationParameters for Parameters {
+        const SCHEMA: &'static [ParameterSchema] = &[
+            ParameterSchema {
+                name: "count",
+                description: "A count.",
+                min: 1.0,
+                max: 10.0,
+            },
+            ParameterSchema {
+                name: "size",
+                description: "A size.",
+                min: 0.0,
+                max: 1.0,
+            },
+        ];
+        const DEFAULT_SCHEME: SchemeName = SchemeName::Standard;
+
+        fn standard_scheme(&self) -> Scheme {
+            Scheme::new(&[])
+        }
+
+        fn explanatory_scheme(&self) -> Scheme {
+            Scheme::new(&[])
+        }
+    }
+
+    #[test]
+    fn the_schema_gives_each_type_and_default() {
+        let schema: Value =
+            serde_json::from_str(&schema_json::<Parameters>()).expect("The schema is JSON.");
+        assert_eq!(schema[0]["type"], "integer");
+        assert_eq!(schema[0]["default"], 3);
+        assert_eq!(schema[1]["type"], "number");
+        assert_eq!(schema[1]["default"], 0.5);
+    }
+
+    #[test]
+    fn a_parameter_outside_its_range_is_rejected() {
+        assert_eq!(validate(&Parameters::default()), Ok(()));
+        let too_large = Parameters {
+            size: 1.5,
+            ..Parameters::default()
+        };
+        assert_eq!(
+            validate(&too_large),
+            Err("The size parameter is from 0 to 1.".to_string())
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use crate::easing::{self, Easing, Linear};
+use crate::Point3d;
+use serde::Deserialize;
+
+// The number of straight pieces each curve segment is measured with when finding its length
+const LENGTH_SAMPLES: usize = 32;
+
+/// A smooth path through 3D control points, followed at a speed set by an easing.
+///
+/// Progress along the path is measured by arc length, so with linear easing a point moves along it at
+/// a constant speed however the control points are spaced. Other easings speed up and slow down
+/// along the path in the same way that they do between keyframes. Easings that overshoot stop at
+/// the ends of the path.
+#[derive(Deserialize)]
+#[serde(try_from = "PathDescription")]
+pub struct Path {
+    // Every path is stored as cubic Bézier segments, each with four control points
+    segments: Vec<[Point3d; 4]>,
+    // The length of the path from its start to each measured point, segment by segment
+    lengths: Vec<f64>,
+    easing: Box<dyn Easing>,
+}
+
+/// A path as written in JSON, for example
+/// `{"kind": "catmull_rom", "points": [{"x": 0, "y": 0, "z": 0}, ...], "easing": "smoothstep"}`.
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PathDescription {
+    CatmullRom {
+        points: Vec<Point3d>,
+        easing: Option<String>,
+    },
+    CubicBezier {
+        points: Vec<Point3d>,
+        easing: Option<String>,
+    },
+}
+
+impl Path {
+    /// A Catmull-Rom spline passing through every one of at least two points.
+    pub fn catmull_rom(points: &[Point3d]) -> Option<Self> {
+        if points.len() < 2 {
+            return None;
+        }
+        let last = points.len() - 1;
+        let segments = (0..last)
+            .map(|index| {
+                // The tangent at each point is parallel to the line between its neighbours, and the
+                // ends use themselves in place of the missing neighbour
+                let before = &points[index.saturating_sub(1)];
+                let start = &points[index];
+                let end = &points[index + 1];
+                let after = &points[(index + 2).min(last)];
+                [
+                    start.clone(),
+                    start.clone() + (end.clone() - before.clone()) * (1.0 / 6.0),
+                    end.clone() - (after.clone() - start.clone()) * (1.0 / 6.0),
+                    end.clone(),
+                ]
+            })
+            .collect();
+        Some(Self::new(segments))
+    }
+
+    /// Cubic Bézier curves joined end to end, from 3n + 1 points for n curves. Each curve starts at
+    /// the end of the previous one, and the two points between the ends of a curve pull it into shape
+    /// without it passing through them.
+    pub fn cubic_bezier(points: &[Point3d]) -> Option<Self> {
+        if points.len() < 4 || !(points.len() - 1).is_multiple_of(3) {
+            return None;
+        }
+        let segments = points
+            .windows(4)
+            .step_by(3)
+            .map(|window| {
+                [
+                    window[0].clone(),
+                    window[1].clone(),
+                    window[2].clone(),
+                    window[3].clone(),
+                ]
+            })
+            .collect();
+        Some(Self::new(segments))
+    }
+
+    fn new(segments: Vec<[Point3d; 4]>) -> Self {
+        let mut lengths = vec![0.0];
+        let mut previous = segments[0][0].clone();
+        for segment in &segments {
+            for sample in 1..=LENGTH_SAMPLES {
+                let point = bezier_point(segment, sample as f64 / LENGTH_SAMPLES as f64);
+                lengths.push(lengths[lengths.len() - 1] + Point3d::distance(&previous, &point));
+                previous = point;
+            }
+        }
+        Self {
+            segments,
+            lengths,
+            easing: Box::new(Linear),
+        }
+    }
+
+    /// Move along the path with `easing` instead of at a constant speed.
+    pub fn with_easing(self, easing: Box<dyn Easing>) -> Self {
+        Self { easing, ..self }
+    }
+
+    pub fn length(&self) -> f64 {
+        self.lengths[self.lengths.len() - 1]
+    }
+
+    /// The point `progress` of the way along the path, from 0.0 at its start to 1.0 at its end.
+    pub fn point(&self, progress: f64) -> Point3d {
+        let distance = self.easing.ease(progress).clamp(0.0, 1.0) * self.length();
+        // Find the measured piece containing the distance, and the fraction of the way along it
+        let piece = self
+            .lengths
+            .partition_point(|length| *length <= distance)
+            .clamp(1, self.lengths.len() - 1)
+            - 1;
+        let piece_length = self.lengths[piece + 1] - self.lengths[piece];
+        let within = if piece_length > 0.0 {
+            (distance - self.lengths[piece]) / piece_length
+        } else {
+            0.0
+        };
+        let segment = piece / LENGTH_SAMPLES;
+        let t = ((piece % LENGTH_SAMPLES) as f64 + within) / LENGTH_SAMPLES as f64;
+        bezier_point(&self.segments[segment], t)
+    }
+}
+
+fn bezier_point(segment: &[Point3d; 4], t: f64) -> Point3d {
+    let s = 1.0 - t;
+    segment[0].clone() * (s * s * s)
+        + segment[1].clone() * (3.0 * s * s * t)
+        + segment[2].clone() * (3.0 * s * t * t)
+        + segment[3].clone() * (t * t * t)
+}
+
+impl TryFrom<PathDescription> for Path {
+    type Error = String;
+
+    fn try_from(description: PathDescription) -> Result<Self, Self::Error> {
+        let (path, easing) = match description {
+            PathDescription::CatmullRom { points, easing } => (
+                Self::catmull_rom(&points).ok_or("A Catmull-Rom path has at least two points.")?,
+                easing,
+            ),
+            PathDescription::CubicBezier { points, easing } => (
+                Self::cubic_bezier(&points)
+                    .ok_or("A cubic Bézier path has 3n + 1 points, and at least four.")?,
+                easing,
+            ),
+        };
+        Ok(match easing {
+            Some(name) => path.with_easing(
+                easing::by_name(&name).ok_or(format!("There is no easing called \"{name}\"."))?,
+            ),
+            None => path,
+        })
+    }
+}
@@ -0,0 +1,35 @@
+use serde::Serialize;
+
+/// A named stretch of an animation, running from `start` to `end` as fractions of the way through,
+/// with a caption describing what happens during it.
+#[derive(Serialize)]
+pub struct Phase {
+    pub name: &'static str,
+    pub start: f64,
+    pub end: f64,
+    pub caption: &'static str,
+}
+
+impl Phase {
+    /// One phase between each pair of consecutive rows of a keyframe table, taking names and captions
+    /// in order.
+    pub fn between_keyframes<const TRACKS: usize>(
+        rows: &[(f64, [f64; TRACKS])],
+        descriptions: &[(&'static str, &'static str)],
+    ) -> Vec<Self> {
+        assert_eq!(
+            rows.len(),
+            descriptions.len() + 1,
+            "There is one phase between each pair of keyframes."
+        );
+        rows.windows(2)
+            .zip(descriptions)
+            .map(|(pair, (name, caption))| Self {
+                name,
+                start: pair[0].0,
+                end: pair[1].0,
+                caption,
+            })
+            .collect()
+    }
+}
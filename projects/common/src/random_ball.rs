@@ -0,0 +1,289 @@
+use crate::explode::{Explode, ExplodeOptions, Stagger};
+use crate::scene_graph::Node;
+use crate::{tag_set, Group, Point3d, Sphere};
+use std::f64::consts::TAU;
+
+// How much higher each layer of the ball stands than the layer below, so that a later part covers an
+// earlier one where they meet
+const LAYER_HEIGHT: f64 = 0.0001;
+// The greys of the parts, dealt out at random so that no two parts share one, except that the caps
+// that close a band take the grey of the ball
+const GREYS: [u8; 8] = [40, 70, 100, 130, 160, 190, 220, 250];
+const MAX_PATCHES: u32 = 6;
+// Just enough to give the band sphere a direction to move in as the ball comes apart, too little to
+// change how the band looks
+const BAND_NUDGE: f64 = 0.000001;
+
+/// A novelty ball built at random from a seed, with an animation that takes it apart and puts it back
+/// together. The same seed always gives the same ball, so balls can be kept as regression fixtures.
+///
+/// The ball is a unit sphere with parts laid over it in layers. Each part is a sphere of nearly unit
+/// radius, moved a little off centre so that it stands above the ball over a round patch and sinks
+/// below it everywhere else. Some balls also have a band: a sphere slightly larger than the ball,
+/// with a cap at each end of an axis, in the grey of the ball, covering it all but a strip around the
+/// middle. The parts come off from the outside in, and the ball itself stays where it is.
+pub struct RandomBall {
+    explode: Explode,
+}
+
+impl RandomBall {
+    pub fn new(seed: u32) -> Self {
+        let mut random = Random::new(seed);
+        let mut greys = GREYS.to_vec();
+        random.shuffle(&mut greys);
+        let ball_grey = greys.pop().expect("There is a grey for every part.");
+        let mut spheres = vec![sphere(
+            "ball sphere",
+            &["ball"],
+            Point3d::origin(),
+            1.0,
+            ball_grey,
+        )];
+        let mut layer = 0;
+        let mut next_layer = || {
+            layer += 1;
+            layer as f64 * LAYER_HEIGHT
+        };
+        if random.below(2) == 1 {
+            let axis = random.direction();
+            let half_width = random.range(0.1, 0.3);
+            let offset = random.range(0.002, 0.005);
+            let height = next_layer();
+            spheres.push(sphere(
+                "band sphere",
+                &["band"],
+                random.direction() * BAND_NUDGE,
+                1.0 + height,
+                greys.pop().expect("There is a grey for every part."),
+            ));
+            let height = next_layer();
+            for (index, end) in [1.0, -1.0].into_iter().enumerate() {
+                spheres.push(cap(
+                    &format!("band cap {index}"),
+                    &["ball", "band-cap"],
+                    &(axis.clone() * end),
+                    offset,
+                    TAU / 4.0 - half_width,
+                    height,
+                    ball_grey,
+                ));
+            }
+        }
+        for index in 0..2 + random.below(MAX_PATCHES - 1) {
+            let direction = random.direction();
+            let offset = random.range(0.002, 0.006);
+            let angle = random.range(0.15, 0.6);
+            spheres.push(cap(
+                &format!("patch sphere {index}"),
+                &["patch"],
+                &direction,
+                offset,
+                angle,
+                next_layer(),
+                greys.pop().expect("There is a grey for every part."),
+            ));
+        }
+        let options = ExplodeOptions {
+            centre: Some(Point3d::origin()),
+            stagger: Stagger::OutsideIn,
+            stagger_spread: 0.5,
+            ..ExplodeOptions::default()
+        };
+        Self {
+            explode: Explode::new(Group::new(spheres), options).expect("The options are valid."),
+        }
+    }
+
+    pub fn duration_seconds(&self) -> f64 {
+        self.explode.duration_seconds()
+    }
+
+    pub fn frame(&self, animation_fraction: f64) -> Group {
+        Node::from(self.explode.frame(animation_fraction))
+            .rotate(TAU * animation_fraction, &Point3d::y_axis())
+            .expect("The y_axis vector is not zero.")
+            .rotate(TAU / 8.0, &Point3d::x_axis())
+            .expect("The x_axis vector is not zero.")
+            .scale(0.25)
+            .flatten()
+    }
+}
+
+fn sphere(name: &str, tags: &[&str], centre: Point3d, radius: f64, grey_value: u8) -> Sphere {
+    Sphere {
+        centre,
+        radius,
+        grey_value,
+        name: Some(name.to_string()),
+        tags: tag_set(tags),
+    }
+}
+
+// A sphere moved `offset` along `direction` and shrunk so that it stands `height` above the unit ball
+// at `angle` from `direction`, higher within that angle and lower beyond it
+fn cap(
+    name: &str,
+    tags: &[&str],
+    direction: &Point3d,
+    offset: f64,
+    angle: f64,
+    height: f64,
+    grey_value: u8,
+) -> Sphere {
+    sphere(
+        name,
+        tags,
+        direction.clone() * offset,
+        1.0 + height - offset * angle.cos(),
+        grey_value,
+    )
+}
+
+// SplitMix64, written out here so that a seed gives the same numbers whatever versions of the
+// dependencies are used
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn new(seed: u32) -> Self {
+        Self { state: seed as u64 }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // A number from 0.0 up to but not including 1.0, from the top 53 bits
+    fn fraction(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.fraction()
+    }
+
+    fn below(&mut self, count: u32) -> u32 {
+        (self.next() % count as u64) as u32
+    }
+
+    // A unit vector pointing anywhere with equal chance
+    fn direction(&mut self) -> Point3d {
+        let z = self.range(-1.0, 1.0);
+        let longitude = self.range(0.0, TAU);
+        let radius = (1.0 - z * z).sqrt();
+        Point3d {
+            x: radius * longitude.cos(),
+            y: radius * longitude.sin(),
+            z,
+        }
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            items.swap(index, self.below(index as u32 + 1) as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::ContinuityReport;
+
+    // The centre, radius and grey of every sphere of the ball built from seed 3, part of the way
+    // through coming apart. Any change to how balls are built from a seed shows up here.
+    const SEED_3_AT_0_3: [((f64, f64, f64), f64, u8); 5] = [
+        ((0.0, 0.0, 0.0), 0.25, 190),
+        (
+            (
+                0.04103247613046896,
+                -0.01981969522405167,
+                -0.02340996032311668,
+            ),
+            0.2488934135057202,
+            130,
+        ),
+        (
+            (
+                -0.0003280208274559501,
+                -0.0011105038406872228,
+                -0.00058847213902113,
+            ),
+            0.2488045043761774,
+            220,
+        ),
+        (
+            (
+                -0.4723938934724657,
+                0.22452929662401627,
+                0.20459659426978416,
+            ),
+            0.24913013892642671,
+            100,
+        ),
+        (
+            (
+                0.2508201552692446,
+                -0.07584490257537216,
+                -0.09298819690748154,
+            ),
+            0.24947183782870586,
+            160,
+        ),
+    ];
+
+    #[test]
+    fn a_seed_always_builds_the_same_ball() {
+        let spheres: Vec<((f64, f64, f64), f64, u8)> = RandomBall::new(3)
+            .frame(0.3)
+            .spheres
+            .into_iter()
+            .map(|sphere| {
+                let Point3d { x, y, z } = sphere.centre;
+                ((x, y, z), sphere.radius, sphere.grey_value)
+            })
+            .collect();
+        assert_eq!(spheres, SEED_3_AT_0_3);
+        for seed in 0..20 {
+            let frame = |seed| RandomBall::new(seed).frame(0.3).to_json();
+            assert_ne!(frame(seed), frame(seed + 1));
+        }
+    }
+
+    #[test]
+    fn every_ball_comes_apart_and_back_together() {
+        for seed in 0..20 {
+            let ball = RandomBall::new(seed);
+            let report = ContinuityReport::new(ball.explode.tracks(), |animation_fraction| {
+                ball.frame(animation_fraction)
+            });
+            for track in report.tracks {
+                assert_eq!(
+                    track.discontinuities,
+                    vec![],
+                    "{} is continuous.",
+                    track.track
+                );
+            }
+            assert_eq!(report.unmatched_at_loop, Vec::<String>::new());
+        }
+    }
+
+    #[test]
+    fn every_cap_stands_above_the_ball_on_its_own_side_only() {
+        for seed in 0..20 {
+            for sphere in RandomBall::new(seed).explode.frame(0.0).spheres {
+                let offset = Point3d::distance(&sphere.centre, &Point3d::origin());
+                if sphere.tags.contains("patch") || sphere.tags.contains("band-cap") {
+                    assert!(offset + sphere.radius > 1.0);
+                    assert!(sphere.radius - offset < 1.0);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::clock::Clock;
+use crate::motion_blur::MotionBlur;
+use crate::palette::{Scheme, SchemeChoice, SchemeName};
+use crate::parameters::{self, AnimationParameters};
+use crate::phase::Phase;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// How to render an animation, read from JSON. Every field is optional.
+///
+/// For example, `{"hold_ms": 1500, "phase_delay_scales": {"Ball lifts": 2.0}, "play_once": true}`
+/// shows the assembled ball for an extra one and a half seconds, plays the named phase at half speed,
+/// and stops on the finished ball.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields, bound = "P: AnimationParameters")]
+pub struct RenderOptions<P> {
+    pub frames_per_second: f64,
+    /// How many times the animation plays, or 0 to play forever.
+    pub loop_count: u32,
+    /// Play the animation once and finish on the assembled ball, in its standard colours unless a
+    /// colour scheme is chosen.
+    pub play_once: bool,
+    /// Extra time to show the first, assembled frame, in milliseconds.
+    pub hold_ms: u32,
+    /// Multiply the delay of every frame within a named phase, so that 2.0 plays the phase at half
+    /// speed. The frames are sampled at the same times, so the phase is slowed without adding frames.
+    pub phase_delay_scales: HashMap<String, f64>,
+    /// Blur each frame over its shutter interval, such as `{"shutter_angle": 180, "samples": 8}`.
+    pub motion_blur: Option<MotionBlur>,
+    /// Colour the spheres with `"standard"`, `"explanatory"` or a custom scheme instead of the
+    /// animation's default.
+    pub colour_scheme: Option<SchemeChoice>,
+    /// Vary the ball itself, such as `{"segments": 6}` for the beachball. `parameter_schema_json`
+    /// lists what each animation can vary.
+    pub parameters: P,
+}
+
+impl<P: AnimationParameters> Default for RenderOptions<P> {
+    fn default() -> Self {
+        Self {
+            frames_per_second: crate::DEFAULT_FRAMES_PER_SECOND,
+            loop_count: 0,
+            play_once: false,
+            hold_ms: 0,
+            phase_delay_scales: HashMap::new(),
+            motion_blur: None,
+            colour_scheme: None,
+            parameters: P::default(),
+        }
+    }
+}
+
+impl<P: AnimationParameters> RenderOptions<P> {
+    pub fn from_json(json: &str, phases: &[Phase]) -> Result<Self, String> {
+        let options: Self = serde_json::from_str(json).map_err(|error| error.to_string())?;
+        options.validate(phases)?;
+        Ok(options)
+    }
+
+    pub fn validate(&self, phases: &[Phase]) -> Result<(), String> {
+        if !(self.frames_per_second.is_finite() && self.frames_per_second > 0.0) {
+            return Err("The frame rate is a positive number.".to_string());
+        }
+        for (name, scale) in &self.phase_delay_scales {
+            if !phases.iter().any(|phase| phase.name == name) {
+                return Err(format!("There is no phase called \"{name}\"."));
+            }
+            if !(scale.is_finite() && *scale > 0.0) {
+                return Err(format!(
+                    "The delay scale for \"{name}\" is a positive number."
+                ));
+            }
+        }
+        if let Some(motion_blur) = &self.motion_blur {
+            motion_blur.validate()?;
+        }
+        parameters::validate(&self.parameters)
+    }
+
+    /// The colour schemes of the animation and of the finished frame that playing once stops on. A
+    /// chosen scheme colours both, and otherwise the animation has its default scheme and finishes in
+    /// the standard one.
+    pub fn colour_schemes(&self) -> (Scheme, Scheme) {
+        match &self.colour_scheme {
+            Some(choice) => (
+                choice.scheme(&self.parameters),
+                choice.scheme(&self.parameters),
+            ),
+            None => (
+                SchemeChoice::default_for::<P>().scheme(&self.parameters),
+                SchemeChoice::Named(SchemeName::Standard).scheme(&self.parameters),
+            ),
+        }
+    }
+
+    /// Render the frame starting at `animation_fraction` with `render`, blurring it if asked to.
+    pub fn frame_pixels(
+        &self,
+        clock: &Clock,
+        animation_fraction: f64,
+        render: impl Fn(f64) -> Vec<u8>,
+    ) -> Vec<u8> {
+        match &self.motion_blur {
+            Some(motion_blur) => {
+                motion_blur.render(animation_fraction, clock.frame_fraction(), render)
+            }
+            None => render(animation_fraction),
+        }
+    }
+
+    pub fn clock(&self, duration_seconds: f64) -> Clock {
+        Clock::new(duration_seconds, self.frames_per_second)
+            .expect("The duration and the validated frame rate are positive.")
+    }
+
+    pub fn loop_count(&self) -> u32 {
+        match self.play_once {
+            true => 1,
+            false => self.loop_count,
+        }
+    }
+
+    /// The delay of every frame from `clock`, in thousandths of a second, after holding the first
+    /// frame and scaling the frames of each phase.
+    pub fn delays(&self, clock: &Clock, phases: &[Phase]) -> Vec<u16> {
+        (0..clock.frame_count())
+            .map(|frame_index| {
+                let animation_fraction = clock.animation_fraction(frame_index);
+                let scale = phases
+                    .iter()
+                    .find(|phase| {
+                        phase.start <= animation_fraction && animation_fraction < phase.end
+                    })
+                    .and_then(|phase| self.phase_delay_scales.get(phase.name))
+                    .unwrap_or(&1.0);
+                let hold = match frame_index {
+                    0 => self.hold_ms as f64,
+                    _ => 0.0,
+                };
+                (clock.delay(frame_index) as f64 * scale + hold)
+                    .round()
+                    .min(u16::MAX as f64) as u16
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,317 @@
+use crate::easing::{self, Easing, PositiveCos};
+use crate::palette::SchemeName;
+use crate::path::Path;
+use crate::timeline::{Keyframe, Timeline};
+use crate::{Group, Point3d, Sphere};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::f64::consts::TAU;
+use std::rc::Rc;
+
+// The name of the built-in track whose value is the fraction of the way through the animation
+const TIME: &str = "time";
+
+/// An animation described in JSON rather than in Rust, loaded into a `Group` for each frame.
+///
+/// A scene lists groups of spheres. Each sphere has a centre, a radius, a grey value, an optional
+/// name and tags, and a chain of transforms applied to its centre in order. Each group then has a
+/// chain of transforms applied to all of its spheres, which also scale their radii. Any number in a
+/// centre or a transform can follow a named track instead of being fixed, as
+/// `{"track": "orbit", "scale": 4.0, "offset": 0.001}` for 4 × orbit + 0.001. Tracks are keyframed
+/// like `Timeline`s, and the track `time` is the fraction of the way through the animation.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    pub duration_seconds: f64,
+    #[serde(default)]
+    pub tracks: BTreeMap<String, TrackDescription>,
+    pub groups: Vec<GroupDescription>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrackDescription {
+    pub keyframes: Vec<KeyframeDescription>,
+    /// The name of an easing, as `easing::by_name` reads it, for keyframes that do not give their
+    /// own. The default is `positive-cos`.
+    pub easing: Option<String>,
+}
+
+/// The fraction of the way through the animation and the value at that point, as `[time, value]`,
+/// or as `[time, value, easing]` to move on to the next keyframe with an easing of its own.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum KeyframeDescription {
+    Plain(f64, f64),
+    Eased(f64, f64, String),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GroupDescription {
+    pub spheres: Vec<SphereDescription>,
+    #[serde(default)]
+    pub transforms: Vec<Transform>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SphereDescription {
+    pub centre: PointDescription,
+    pub radius: Scalar,
+    pub grey: Grey,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    #[serde(default)]
+    pub transforms: Vec<Transform>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PointDescription {
+    pub x: Scalar,
+    pub y: Scalar,
+    pub z: Scalar,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Scalar {
+    Fixed(f64),
+    Track {
+        track: String,
+        #[serde(default = "one")]
+        scale: f64,
+        #[serde(default)]
+        offset: f64,
+    },
+}
+
+/// A grey value that is the same in every colour scheme, or one for each scheme.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Grey {
+    Fixed(u8),
+    Schemes { standard: u8, explanatory: u8 },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    /// Move the centre to the same direction from the origin at a distance of 1. A centre at the
+    /// origin stays there.
+    Normalise,
+    /// Turn about an axis through the origin, by a number of whole turns.
+    Rotate {
+        axis: Point3d,
+        turns: Scalar,
+    },
+    Scale(Scalar),
+    Translate(PointDescription),
+    /// Move by the point `progress` of the way along a path.
+    FollowPath {
+        path: Path,
+        progress: Scalar,
+    },
+}
+
+/// A loaded scene, ready to give the `Group` at any point in the animation.
+pub struct Scene {
+    pub duration_seconds: f64,
+    tracks: BTreeMap<String, Timeline>,
+    groups: Vec<GroupDescription>,
+}
+
+impl Scene {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let description: SceneDescription =
+            serde_json::from_str(json).map_err(|error| error.to_string())?;
+        Self::new(description)
+    }
+
+    pub fn new(description: SceneDescription) -> Result<Self, String> {
+        if !(description.duration_seconds.is_finite() && description.duration_seconds > 0.0) {
+            return Err("The duration is a positive number of seconds.".to_string());
+        }
+        let mut tracks = BTreeMap::new();
+        for (name, track) in description.tracks {
+            tracks.insert(name.clone(), load_track(&name, track)?);
+        }
+        if tracks.contains_key(TIME) {
+            return Err(format!("The track \"{TIME}\" is built in."));
+        }
+
+        for group in &description.groups {
+            let transforms = group
+                .spheres
+                .iter()
+                .flat_map(|sphere| &sphere.transforms)
+                .chain(&group.transforms);
+            for transform in transforms {
+                check_transform(transform, &tracks)?;
+            }
+            for sphere in &group.spheres {
+                for scalar in [
+                    &sphere.centre.x,
+                    &sphere.centre.y,
+                    &sphere.centre.z,
+                    &sphere.radius,
+                ] {
+                    check_scalar(scalar, &tracks)?;
+                }
+            }
+        }
+
+        Ok(Self {
+            duration_seconds: description.duration_seconds,
+            tracks,
+            groups: description.groups,
+        })
+    }
+
+    /// The spheres of the scene at `animation_fraction`, with grey values from `scheme`.
+    pub fn frame(&self, animation_fraction: f64, scheme: SchemeName) -> Group {
+        let sample = |scalar: &Scalar| match scalar {
+            Scalar::Fixed(value) => *value,
+            Scalar::Track {
+                track,
+                scale,
+                offset,
+            } => {
+                let value = match track.as_str() {
+                    TIME => animation_fraction,
+                    _ => self.tracks[track].sample(animation_fraction),
+                };
+                value * scale + offset
+            }
+        };
+        let point = |point: &PointDescription| Point3d {
+            x: sample(&point.x),
+            y: sample(&point.y),
+            z: sample(&point.z),
+        };
+        let mut spheres = vec![];
+        for group in &self.groups {
+            let mut group_spheres = vec![];
+            for sphere in &group.spheres {
+                let mut centre = point(&sphere.centre);
+                for transform in &sphere.transforms {
+                    centre = match transform {
+                        Transform::Normalise => centre.normalise().unwrap_or(centre),
+                        Transform::Rotate { axis, turns } => centre
+                            .rotate(sample(turns) * TAU, axis)
+                            .expect("The axis was checked when loading."),
+                        Transform::Scale(scale) => centre * sample(scale),
+                        Transform::Translate(offset) => centre + point(offset),
+                        Transform::FollowPath { path, progress } => {
+                            centre + path.point(sample(progress))
+                        }
+                    };
+                }
+                group_spheres.push(Sphere {
+                    centre,
+                    radius: sample(&sphere.radius),
+                    grey_value: match (&sphere.grey, scheme) {
+                        (Grey::Fixed(grey_value), _) => *grey_value,
+                        (Grey::Schemes { standard, .. }, SchemeName::Standard) => *standard,
+                        (Grey::Schemes { explanatory, .. }, SchemeName::Explanatory) => {
+                            *explanatory
+                        }
+                    },
+                    name: sphere.name.clone(),
+                    tags: sphere.tags.clone(),
+                });
+            }
+            let mut group_spheres = Group::new(group_spheres);
+            for transform in &group.transforms {
+                group_spheres = match transform {
+                    Transform::Normalise => Group::new(
+                        group_spheres
+                            .spheres
+                            .into_iter()
+                            .map(|sphere| Sphere {
+                                centre: sphere.centre.normalise().unwrap_or(sphere.centre),
+                                ..sphere
+                            })
+                            .collect(),
+                    ),
+                    Transform::Rotate { axis, turns } => group_spheres
+                        .rotate(sample(turns) * TAU, axis)
+                        .expect("The axis was checked when loading."),
+                    Transform::Scale(scale) => group_spheres * sample(scale),
+                    Transform::Translate(offset) => group_spheres + point(offset),
+                    Transform::FollowPath { path, progress } => {
+                        group_spheres + path.point(sample(progress))
+                    }
+                };
+            }
+            spheres.extend(group_spheres.spheres);
+        }
+        Group::new(spheres)
+    }
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+fn load_track(name: &str, track: TrackDescription) -> Result<Timeline, String> {
+    let track_easing = match &track.easing {
+        Some(easing) => easing_by_name(easing)?,
+        None => Rc::new(PositiveCos),
+    };
+    let keyframes = track
+        .keyframes
+        .into_iter()
+        .map(|keyframe| {
+            Ok(match keyframe {
+                KeyframeDescription::Plain(time, value) => Keyframe {
+                    easing: track_easing.clone(),
+                    ..Keyframe::new(time, value)
+                },
+                KeyframeDescription::Eased(time, value, easing) => Keyframe {
+                    easing: easing_by_name(&easing)?,
+                    ..Keyframe::new(time, value)
+                },
+            })
+        })
+        .collect::<Result<Vec<Keyframe>, String>>()?;
+    Timeline::try_new(keyframes)
+        .map_err(|error| format!("The track \"{name}\" is invalid. {error}"))
+}
+
+fn easing_by_name(name: &str) -> Result<Rc<dyn Easing>, String> {
+    easing::by_name(name)
+        .map(Rc::from)
+        .ok_or(format!("There is no easing called \"{name}\"."))
+}
+
+fn check_scalar(scalar: &Scalar, tracks: &BTreeMap<String, Timeline>) -> Result<(), String> {
+    match scalar {
+        Scalar::Track { track, .. } if track != TIME && !tracks.contains_key(track) => {
+            Err(format!("There is no track called \"{track}\"."))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_transform(
+    transform: &Transform,
+    tracks: &BTreeMap<String, Timeline>,
+) -> Result<(), String> {
+    match transform {
+        Transform::Normalise => Ok(()),
+        Transform::Rotate { axis, turns } => {
+            if *axis == Point3d::origin() {
+                return Err("A rotation axis is not zero.".to_string());
+            }
+            check_scalar(turns, tracks)
+        }
+        Transform::Scale(scale) => check_scalar(scale, tracks),
+        Transform::Translate(offset) => [&offset.x, &offset.y, &offset.z]
+            .into_iter()
+            .try_for_each(|scalar| check_scalar(scalar, tracks)),
+        Transform::FollowPath { progress, .. } => check_scalar(progress, tracks),
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{Group, Point3d, Sphere};
+
+/// A node of a scene graph, holding spheres and further nodes and placing them with a local
+/// transform.
+///
+/// Each node's transform is relative to its parent, so a part of an animation made of several spheres
+/// can be built about its own origin and then turned, scaled and moved as a unit. `flatten` applies
+/// every transform on the way up to the root, giving the spheres in world space ready to render.
+#[derive(Clone)]
+pub struct Node {
+    children: Vec<Child>,
+    transform: Vec<Step>,
+}
+
+#[derive(Clone)]
+pub enum Child {
+    Sphere(Sphere),
+    Node(Node),
+}
+
+// One step of a local transform, applied in the order the steps were added
+#[derive(Clone)]
+enum Step {
+    Rotate { angle: f64, axis: Point3d },
+    Scale(f64),
+    Translate(Point3d),
+}
+
+impl Node {
+    pub fn new(children: Vec<Child>) -> Self {
+        Self {
+            children,
+            transform: vec![],
+        }
+    }
+
+    /// Turn the node about an axis through its parent's origin, after its existing transform.
+    pub fn rotate(mut self, angle: f64, axis: &Point3d) -> Option<Self> {
+        if *axis == Point3d::origin() {
+            return None;
+        }
+        self.transform.push(Step::Rotate {
+            angle,
+            axis: axis.clone(),
+        });
+        Some(self)
+    }
+
+    /// Scale the node about its parent's origin, radii included, after its existing transform.
+    pub fn scale(mut self, factor: f64) -> Self {
+        self.transform.push(Step::Scale(factor));
+        self
+    }
+
+    /// Move the node by `offset`, after its existing transform.
+    pub fn translate(mut self, offset: Point3d) -> Self {
+        self.transform.push(Step::Translate(offset));
+        self
+    }
+
+    /// The spheres of the node and all of its descendants, in the order they were added, in the
+    /// coordinates of the node's parent.
+    pub fn flatten(&self) -> Group {
+        let mut group = Group::new(vec![]);
+        for child in &self.children {
+            group = group
+                + match child {
+                    Child::Sphere(sphere) => Group::new(vec![sphere.clone()]),
+                    Child::Node(node) => node.flatten(),
+                };
+        }
+        for step in &self.transform {
+            group = match step {
+                Step::Rotate { angle, axis } => group
+                    .rotate(*angle, axis)
+                    .expect("The axis was checked when the rotation was added."),
+                Step::Scale(factor) => group * *factor,
+                Step::Translate(offset) => group + offset.clone(),
+            };
+        }
+        group
+    }
+}
+
+impl From<Group> for Node {
+    fn from(group: Group) -> Self {
+        Self::new(group.spheres.into_iter().map(Child::Sphere).collect())
+    }
+}
+
+impl From<Sphere> for Child {
+    fn from(sphere: Sphere) -> Self {
+        Self::Sphere(sphere)
+    }
+}
+
+impl From<Node> for Child {
+    fn from(node: Node) -> Self {
+        Self::Node(node)
+    }
+}
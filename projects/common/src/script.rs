@@ -0,0 +1,336 @@
+use crate::easing::{self, Easing, PositiveCos};
+use crate::scene_graph::{Child, Node};
+use crate::timeline::{Keyframe, Timeline};
+use crate::{Group, Point3d, Sphere};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope, AST, INT};
+use std::collections::BTreeSet;
+use std::f64::consts::TAU;
+use std::rc::Rc;
+
+// A limit on the work done by each call into a script, so that a script that never finishes stops
+// with an error instead of freezing the page
+const MAX_OPERATIONS: u64 = 10_000_000;
+const MAX_EXPRESSION_DEPTH: usize = 128;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// An animation written as a Rhai script instead of in Rust, so that it can be changed without
+/// recompiling.
+///
+/// The script defines `fn duration_seconds()`, giving the length of the animation, and
+/// `fn frame(animation_fraction)`, giving the `Group` to draw at that point in it. Scripts can build
+/// points, spheres and groups and combine them with the same operators as the Rust:
+///
+/// - `point(x, y, z)`, `origin()`, `x_axis()`, `y_axis()` and `z_axis()`, with `.x`, `.y` and `.z`,
+///   `+`, `-`, `* number`, `.normalise()`, `.rotate(angle, axis)` and `distance(a, b)`
+/// - `sphere(centre, radius, grey)`, with `.centre`, `.radius`, `.grey`, `.name` and `.tags`,
+///   `.named(name)`, `.tagged(tag)`, `+ point`, `- point`, `* number` and `.rotate(angle, axis)`
+/// - `group()` or `group([spheres])`, with `.push(sphere)`, `.len()`, `+ group`, `+ point`,
+///   `- point`, `* number` and `.rotate(angle, axis)`
+/// - `node([children])`, where the children are spheres, groups and nodes, with `.rotate(angle, axis)`,
+///   `.scale(factor)` and `.translate(offset)` applied to the node as a unit in the order they are
+///   called, and `.flatten()` giving the group of its spheres
+/// - `keyframes([[time, value], ...])` or `keyframes([[time, value], ...], easing)`, giving a timeline
+///   with `.sample(animation_fraction)`, where a keyframe given as `[time, value, easing]` moves on to
+///   the next with an easing of its own
+/// - `ease(easing, progress)`, with easings named as for `easing::by_name`, and `TAU()`
+///
+/// Angles are in radians, and `TAU()` is a whole turn.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    pub duration_seconds: f64,
+}
+
+impl Script {
+    pub fn new(source: &str) -> Result<Self, String> {
+        let engine = engine();
+        let ast = engine.compile(source).map_err(|error| error.to_string())?;
+        let duration_seconds = engine
+            .call_fn(&mut Scope::new(), &ast, "duration_seconds", ())
+            .and_then(number)
+            .map_err(|error| format!("fn duration_seconds(): {error}"))?;
+        if !(duration_seconds.is_finite() && duration_seconds > 0.0) {
+            return Err("The duration is a positive number of seconds.".to_string());
+        }
+        Ok(Self {
+            engine,
+            ast,
+            duration_seconds,
+        })
+    }
+
+    pub fn frame(&self, animation_fraction: f64) -> Result<Group, String> {
+        self.engine
+            .call_fn(&mut Scope::new(), &self.ast, "frame", (animation_fraction,))
+            .map_err(|error| format!("fn frame({animation_fraction}): {error}"))
+    }
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    // Chains of transforms nest deeper than Rhai allows by default
+    engine.set_max_expr_depths(MAX_EXPRESSION_DEPTH, MAX_EXPRESSION_DEPTH);
+    engine.register_fn("TAU", || TAU);
+
+    engine
+        .register_type_with_name::<Point3d>("Point")
+        .register_fn(
+            "point",
+            |x: Dynamic, y: Dynamic, z: Dynamic| -> ScriptResult<Point3d> {
+                Ok(Point3d {
+                    x: number(x)?,
+                    y: number(y)?,
+                    z: number(z)?,
+                })
+            },
+        )
+        .register_fn("origin", Point3d::origin)
+        .register_fn("x_axis", Point3d::x_axis)
+        .register_fn("y_axis", Point3d::y_axis)
+        .register_fn("z_axis", Point3d::z_axis)
+        .register_get("x", |p: &mut Point3d| p.x)
+        .register_set("x", |p: &mut Point3d, x: Dynamic| -> ScriptResult<()> {
+            p.x = number(x)?;
+            Ok(())
+        })
+        .register_get("y", |p: &mut Point3d| p.y)
+        .register_set("y", |p: &mut Point3d, y: Dynamic| -> ScriptResult<()> {
+            p.y = number(y)?;
+            Ok(())
+        })
+        .register_get("z", |p: &mut Point3d| p.z)
+        .register_set("z", |p: &mut Point3d, z: Dynamic| -> ScriptResult<()> {
+            p.z = number(z)?;
+            Ok(())
+        })
+        .register_fn("+", |a: Point3d, b: Point3d| a + b)
+        .register_fn("-", |a: Point3d, b: Point3d| a - b)
+        .register_fn("*", |a: Point3d, b: f64| a * b)
+        .register_fn("*", |a: Point3d, b: INT| a * b as f64)
+        .register_fn("normalise", |p: &mut Point3d| -> ScriptResult<Point3d> {
+            p.normalise()
+                .ok_or_else(|| "The origin has no direction to normalise.".into())
+        })
+        .register_fn(
+            "rotate",
+            |p: &mut Point3d, angle: Dynamic, axis: Point3d| -> ScriptResult<Point3d> {
+                p.rotate(number(angle)?, &axis).ok_or_else(zero_axis)
+            },
+        )
+        .register_fn("distance", |a: Point3d, b: Point3d| {
+            Point3d::distance(&a, &b)
+        })
+        .register_fn("to_string", |p: &mut Point3d| p.to_string());
+
+    engine
+        .register_type_with_name::<Sphere>("Sphere")
+        .register_fn(
+            "sphere",
+            |centre: Point3d, radius: Dynamic, grey: INT| -> ScriptResult<Sphere> {
+                Ok(Sphere {
+                    centre,
+                    radius: number(radius)?,
+                    grey_value: grey_value(grey)?,
+                    name: None,
+                    tags: BTreeSet::new(),
+                })
+            },
+        )
+        .register_get_set(
+            "centre",
+            |s: &mut Sphere| s.centre.clone(),
+            |s: &mut Sphere, centre: Point3d| s.centre = centre,
+        )
+        .register_get("radius", |s: &mut Sphere| s.radius)
+        .register_set(
+            "radius",
+            |s: &mut Sphere, radius: Dynamic| -> ScriptResult<()> {
+                s.radius = number(radius)?;
+                Ok(())
+            },
+        )
+        .register_get("grey", |s: &mut Sphere| s.grey_value as INT)
+        .register_set("grey", |s: &mut Sphere, grey: INT| -> ScriptResult<()> {
+            s.grey_value = grey_value(grey)?;
+            Ok(())
+        })
+        .register_get("name", |s: &mut Sphere| {
+            s.name.clone().map_or(Dynamic::UNIT, Dynamic::from)
+        })
+        .register_fn("named", |s: &mut Sphere, name: &str| Sphere {
+            name: Some(name.to_string()),
+            ..s.clone()
+        })
+        .register_get("tags", |s: &mut Sphere| {
+            s.tags.iter().cloned().map(Dynamic::from).collect::<Array>()
+        })
+        .register_fn("tagged", |s: &mut Sphere, tag: &str| {
+            let mut sphere = s.clone();
+            sphere.tags.insert(tag.to_string());
+            sphere
+        })
+        .register_fn("+", |s: Sphere, p: Point3d| s + p)
+        .register_fn("-", |s: Sphere, p: Point3d| s - p)
+        .register_fn("*", |s: Sphere, b: f64| s * b)
+        .register_fn("*", |s: Sphere, b: INT| s * b as f64)
+        .register_fn(
+            "rotate",
+            |s: &mut Sphere, angle: Dynamic, axis: Point3d| -> ScriptResult<Sphere> {
+                s.rotate(number(angle)?, &axis).ok_or_else(zero_axis)
+            },
+        )
+        .register_fn("to_string", |s: &mut Sphere| s.to_string());
+
+    engine
+        .register_type_with_name::<Group>("Group")
+        .register_fn("group", || Group::new(vec![]))
+        .register_fn("group", |spheres: Array| -> ScriptResult<Group> {
+            spheres
+                .into_iter()
+                .map(|sphere| {
+                    let type_name = sphere.type_name();
+                    sphere
+                        .try_cast::<Sphere>()
+                        .ok_or_else(|| format!("A group holds spheres, not {type_name}.").into())
+                })
+                .collect::<ScriptResult<Vec<Sphere>>>()
+                .map(Group::new)
+        })
+        .register_fn("push", |g: &mut Group, sphere: Sphere| {
+            g.spheres.push(sphere)
+        })
+        .register_fn("len", |g: &mut Group| g.spheres.len() as INT)
+        .register_fn("+", |a: Group, b: Group| a + b)
+        .register_fn("+", |g: Group, p: Point3d| g + p)
+        .register_fn("-", |g: Group, p: Point3d| g - p)
+        .register_fn("*", |g: Group, b: f64| g * b)
+        .register_fn("*", |g: Group, b: INT| g * b as f64)
+        .register_fn(
+            "rotate",
+            |g: &mut Group, angle: Dynamic, axis: Point3d| -> ScriptResult<Group> {
+                g.rotate(number(angle)?, &axis).ok_or_else(zero_axis)
+            },
+        );
+
+    engine
+        .register_type_with_name::<Node>("Node")
+        .register_fn("node", |children: Array| -> ScriptResult<Node> {
+            children
+                .into_iter()
+                .map(|child| {
+                    let type_name = child.type_name();
+                    if child.is::<Sphere>() {
+                        Ok(Child::Sphere(child.cast()))
+                    } else if child.is::<Node>() {
+                        Ok(Child::Node(child.cast()))
+                    } else if child.is::<Group>() {
+                        Ok(Child::Node(Node::from(child.cast::<Group>())))
+                    } else {
+                        Err(
+                            format!("A node holds spheres, groups and nodes, not {type_name}.")
+                                .into(),
+                        )
+                    }
+                })
+                .collect::<ScriptResult<Vec<Child>>>()
+                .map(Node::new)
+        })
+        .register_fn(
+            "rotate",
+            |n: &mut Node, angle: Dynamic, axis: Point3d| -> ScriptResult<Node> {
+                n.clone()
+                    .rotate(number(angle)?, &axis)
+                    .ok_or_else(zero_axis)
+            },
+        )
+        .register_fn(
+            "scale",
+            |n: &mut Node, factor: Dynamic| -> ScriptResult<Node> {
+                Ok(n.clone().scale(number(factor)?))
+            },
+        )
+        .register_fn("translate", |n: &mut Node, offset: Point3d| {
+            n.clone().translate(offset)
+        })
+        .register_fn("flatten", |n: &mut Node| n.flatten());
+
+    engine
+        .register_type_with_name::<Rc<Timeline>>("Timeline")
+        .register_fn("keyframes", |rows: Array| timeline(rows, None))
+        .register_fn("keyframes", |rows: Array, easing: &str| {
+            timeline(rows, Some(easing))
+        })
+        .register_fn(
+            "sample",
+            |timeline: &mut Rc<Timeline>, animation_fraction: Dynamic| -> ScriptResult<f64> {
+                Ok(timeline.sample(number(animation_fraction)?))
+            },
+        )
+        .register_fn(
+            "ease",
+            |name: &str, progress: Dynamic| -> ScriptResult<f64> {
+                Ok(easing_by_name(name)?.ease(number(progress)?))
+            },
+        );
+
+    engine
+}
+
+// Let scripts write whole numbers wherever a number is expected
+fn number(value: Dynamic) -> ScriptResult<f64> {
+    match value.as_float() {
+        Ok(value) => Ok(value),
+        Err(type_name) => match value.as_int() {
+            Ok(value) => Ok(value as f64),
+            Err(_) => Err(format!("Expected a number, not {type_name}.").into()),
+        },
+    }
+}
+
+fn grey_value(grey: INT) -> ScriptResult<u8> {
+    u8::try_from(grey).map_err(|_| format!("A grey value is from 0 to 255, not {grey}.").into())
+}
+
+fn zero_axis() -> Box<EvalAltResult> {
+    "A rotation axis is not zero.".into()
+}
+
+fn timeline(rows: Array, easing: Option<&str>) -> ScriptResult<Rc<Timeline>> {
+    let track_easing = match easing {
+        Some(name) => easing_by_name(name)?,
+        None => Rc::new(PositiveCos),
+    };
+    let keyframes = rows
+        .into_iter()
+        .map(|row| {
+            let (time, value, easing) = match row.try_cast::<Array>().as_deref() {
+                Some([time, value]) => (time.clone(), value.clone(), track_easing.clone()),
+                Some([time, value, easing]) => {
+                    let name = easing
+                        .clone()
+                        .into_immutable_string()
+                        .map_err(|_| "An easing is named by a string.")?;
+                    (time.clone(), value.clone(), easing_by_name(&name)?)
+                }
+                _ => {
+                    return Err(
+                        "Each keyframe is a [time, value] or [time, value, easing] list.".into(),
+                    )
+                }
+            };
+            Ok(Keyframe {
+                easing,
+                ..Keyframe::new(number(time)?, number(value)?)
+            })
+        })
+        .collect::<ScriptResult<Vec<Keyframe>>>()?;
+    Ok(Rc::new(Timeline::try_new(keyframes)?))
+}
+
+fn easing_by_name(name: &str) -> ScriptResult<Rc<dyn Easing>> {
+    Ok(Rc::from(easing::by_name(name).ok_or_else(|| {
+        format!("There is no easing called \"{name}\".")
+    })?))
+}
@@ -0,0 +1,190 @@
+use crate::frame_options::MAX_SIZE;
+use crate::{grayscale_png, png_data_url};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+// The most pixels in a sheet, which keeps it to 256 MiB, well within the memory a WASM module can use
+const MAX_PIXELS: usize = 1 << 28;
+
+// The descriptor follows the JSON array layout written by Aseprite, which game engines such as
+// Phaser can load directly. Frame rectangles are in pixels and durations are in milliseconds.
+#[derive(Serialize)]
+struct Descriptor {
+    frames: Vec<FrameDescriptor>,
+    meta: Meta,
+}
+
+#[derive(Serialize)]
+struct FrameDescriptor {
+    filename: String,
+    frame: Rectangle,
+    duration: u32,
+}
+
+#[derive(Serialize)]
+struct Rectangle {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Serialize)]
+struct Meta {
+    image: String,
+    size: Size,
+    #[serde(rename = "frameTags")]
+    frame_tags: Vec<FrameTag>,
+}
+
+#[derive(Serialize)]
+struct Size {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Serialize)]
+struct FrameTag {
+    name: String,
+    from: usize,
+    to: usize,
+    direction: String,
+}
+
+#[wasm_bindgen]
+pub struct SpriteSheet {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    descriptor: Descriptor,
+}
+
+impl SpriteSheet {
+    /// Tile the frames left to right and top to bottom into a sheet that is as close to square as possible.
+    ///
+    /// The size of the sheet is checked before any frame is taken from `frames`, so that a tile size
+    /// too large to fit in memory fails before the frames are rendered.
+    pub fn new(
+        animation_name: &str,
+        frames: impl ExactSizeIterator<Item = Vec<u8>>,
+        tile_width: u32,
+        tile_height: u32,
+        frame_duration_ms: u32,
+    ) -> Result<Self, String> {
+        if !(1..=MAX_SIZE).contains(&tile_width) || !(1..=MAX_SIZE).contains(&tile_height) {
+            return Err(format!("The tile size is from 1 to {MAX_SIZE}."));
+        }
+        let frame_count = frames.len();
+        let columns = (frame_count as f64).sqrt().ceil().max(1.0) as u32;
+        let rows = (frame_count as u32).div_ceil(columns).max(1);
+        let too_large = || "The sprite sheet is too large.".to_string();
+        let width = columns.checked_mul(tile_width).ok_or_else(too_large)?;
+        let height = rows.checked_mul(tile_height).ok_or_else(too_large)?;
+        let pixel_count = (width as usize)
+            .checked_mul(height as usize)
+            .filter(|pixel_count| *pixel_count <= MAX_PIXELS)
+            .ok_or_else(too_large)?;
+
+        let mut pixels = vec![0; pixel_count];
+        let mut frame_descriptors = vec![];
+        for (frame_index, frame) in frames.enumerate() {
+            let x = frame_index as u32 % columns * tile_width;
+            let y = frame_index as u32 / columns * tile_height;
+            for (row_index, row) in frame.chunks(tile_width as usize).enumerate() {
+                let start = (y as usize + row_index) * width as usize + x as usize;
+                pixels[start..start + row.len()].copy_from_slice(row);
+            }
+            frame_descriptors.push(FrameDescriptor {
+                filename: format!("{animation_name} {frame_index}"),
+                frame: Rectangle {
+                    x,
+                    y,
+                    w: tile_width,
+                    h: tile_height,
+                },
+                duration: frame_duration_ms,
+            });
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            descriptor: Descriptor {
+                frames: frame_descriptors,
+                meta: Meta {
+                    image: format!("{animation_name}.png"),
+                    size: Size {
+                        w: width,
+                        h: height,
+                    },
+                    frame_tags: vec![FrameTag {
+                        name: animation_name.to_string(),
+                        from: 0,
+                        to: frame_count.saturating_sub(1),
+                        direction: "forward".to_string(),
+                    }],
+                },
+            },
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl SpriteSheet {
+    pub fn data_url(&self) -> String {
+        png_data_url(&grayscale_png(self.width, self.height, &self.pixels))
+    }
+
+    pub fn json(&self) -> String {
+        serde_json::to_string_pretty(&self.descriptor).expect("The descriptor contains no maps.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiles(count: u8, tile_width: u32, tile_height: u32) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|grey_value| vec![grey_value; (tile_width * tile_height) as usize])
+            .collect()
+    }
+
+    #[test]
+    fn frames_are_tiled_into_a_nearly_square_sheet() {
+        let sheet = SpriteSheet::new("test", tiles(5, 3, 2).into_iter(), 3, 2, 40).unwrap();
+        assert_eq!((sheet.width, sheet.height), (9, 4));
+        assert_eq!(&sheet.pixels[..9], &[0, 0, 0, 1, 1, 1, 2, 2, 2]);
+        assert_eq!(&sheet.pixels[27..], &[3, 3, 3, 4, 4, 4, 0, 0, 0]);
+
+        let json: serde_json::Value = serde_json::from_str(&sheet.json()).unwrap();
+        assert_eq!(json["frames"].as_array().unwrap().len(), 5);
+        assert_eq!(json["frames"][4]["frame"]["x"], 3);
+        assert_eq!(json["frames"][4]["frame"]["y"], 2);
+        assert_eq!(json["meta"]["frameTags"][0]["to"], 4);
+    }
+
+    #[test]
+    fn an_empty_or_oversized_tile_is_rejected() {
+        for (tile_width, tile_height) in [(0, 0), (0, 16), (16, 0), (MAX_SIZE + 1, 16)] {
+            assert!(SpriteSheet::new(
+                "test",
+                tiles(4, 1, 1).into_iter(),
+                tile_width,
+                tile_height,
+                40
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn a_sheet_too_large_to_hold_is_rejected_before_any_frame_is_rendered() {
+        let frames = (0..u32::MAX as usize).map(|_| unreachable!("No frame is rendered."));
+        assert_eq!(
+            SpriteSheet::new("test", frames, MAX_SIZE, MAX_SIZE, 40).err(),
+            Some("The sprite sheet is too large.".to_string())
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use crate::easing::{Easing, PositiveCos};
+use std::rc::Rc;
+
+/// A value that changes over one loop of an animation, described by keyframes.
+///
+/// Between two keyframes with the same value, the value holds. Between two keyframes with different
+/// values, it eases from one to the other with the easing of the first of them, which by default
+/// follows half a period of a cosine so that it starts and finishes at rest. The keyframes run from
+/// fraction 0.0 to fraction 1.0 of the animation, and sampling outside that range wraps around.
+pub struct Timeline {
+    keyframes: Vec<Keyframe>,
+}
+
+#[derive(Clone)]
+pub struct Keyframe {
+    pub time: f64,
+    pub value: f64,
+    /// How the value moves from this keyframe to the next. The easing of the last keyframe is never
+    /// used.
+    pub easing: Rc<dyn Easing>,
+}
+
+impl Keyframe {
+    /// A keyframe with the default easing.
+    pub fn new(time: f64, value: f64) -> Self {
+        Self {
+            time,
+            value,
+            easing: Rc::new(PositiveCos),
+        }
+    }
+}
+
+impl Timeline {
+    pub fn new(keyframes: Vec<Keyframe>) -> Self {
+        Self::try_new(keyframes).unwrap_or_else(|message| panic!("{message}"))
+    }
+
+    /// The same as `new` for keyframes that come from outside the program, giving an error instead of
+    /// panicking if they are not in order from 0.0 to 1.0.
+    pub fn try_new(keyframes: Vec<Keyframe>) -> Result<Self, String> {
+        let in_order = keyframes
+            .windows(2)
+            .all(|pair| pair[0].time <= pair[1].time);
+        if keyframes.first().map(|keyframe| keyframe.time) != Some(0.0)
+            || keyframes.last().map(|keyframe| keyframe.time) != Some(1.0)
+            || !in_order
+        {
+            return Err(
+                "The keyframes are in order, starting at 0.0 and finishing at 1.0.".to_string(),
+            );
+        }
+        Ok(Self { keyframes })
+    }
+
+    /// Ease between every pair of keyframes with `easing` instead of the easing of each keyframe.
+    pub fn with_easing(self, easing: Box<dyn Easing>) -> Self {
+        let easing: Rc<dyn Easing> = Rc::from(easing);
+        Self {
+            keyframes: self
+                .keyframes
+                .into_iter()
+                .map(|keyframe| Keyframe {
+                    easing: easing.clone(),
+                    ..keyframe
+                })
+                .collect(),
+        }
+    }
+
+    /// Take one column of a table of keyframes, where each row is a time followed by the value of
+    /// every track at that time.
+    pub fn from_table<const TRACKS: usize>(rows: &[(f64, [f64; TRACKS])], track: usize) -> Self {
+        Self::new(
+            rows.iter()
+                .map(|(time, values)| Keyframe::new(*time, values[track]))
+                .collect(),
+        )
+    }
+
+    pub fn keyframe_times(&self) -> impl Iterator<Item = f64> + '_ {
+        self.keyframes.iter().map(|keyframe| keyframe.time)
+    }
+
+    pub fn sample(&self, animation_fraction: f64) -> f64 {
+        let a = animation_fraction.rem_euclid(1.0);
+        let next = self
+            .keyframes
+            .iter()
+            .position(|keyframe| keyframe.time > a)
+            .unwrap_or(self.keyframes.len() - 1);
+        let start = &self.keyframes[next - 1];
+        let end = &self.keyframes[next];
+        if start.value == end.value {
+            return start.value;
+        }
+        let progress = (a - start.time) / (end.time - start.time);
+        start.value + (end.value - start.value) * start.easing.ease(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::easing::Linear;
+
+    #[test]
+    fn each_keyframe_eases_to_the_next_with_its_own_easing() {
+        let timeline = Timeline::new(vec![
+            Keyframe {
+                easing: Rc::new(Linear),
+                ..Keyframe::new(0.0, 0.0)
+            },
+            Keyframe::new(0.5, 1.0),
+            Keyframe::new(1.0, 0.0),
+        ]);
+        assert_eq!(timeline.sample(0.125), 0.25);
+        assert_eq!(timeline.sample(0.75), 1.0 - PositiveCos.ease(0.5));
+    }
+
+    #[test]
+    fn keyframes_out_of_order_are_an_error() {
+        let keyframes = |times: &[f64]| {
+            times
+                .iter()
+                .map(|time| Keyframe::new(*time, *time))
+                .collect::<Vec<Keyframe>>()
+        };
+        assert!(Timeline::try_new(keyframes(&[0.0, 0.5, 1.0])).is_ok());
+        for times in [&[0.0, 0.6, 0.4, 1.0][..], &[0.1, 1.0], &[0.0, 0.9], &[]] {
+            assert_eq!(
+                Timeline::try_new(keyframes(times)).err(),
+                Some(
+                    "The keyframes are in order, starting at 0.0 and finishing at 1.0.".to_string()
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::timeline::Timeline;
+use crate::{Group, Point3d};
+use serde::Serialize;
+
+// How far either side of a point the value and slope are measured. The slope is estimated from one
+// side at a time, so that a kink shows up as a difference between the two sides.
+const STEP: f64 = 1e-6;
+// The number of evenly spaced points checked in addition to the keyframes
+const SAMPLES: u32 = 1000;
+const VALUE_TOLERANCE: f64 = 1e-3;
+const SLOPE_TOLERANCE: f64 = 1e-2;
+// How close the scene just before the loop point must be to the first frame
+const LOOP_STEP: f64 = 1e-9;
+const LOOP_TOLERANCE: f64 = 1e-6;
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Discontinuity {
+    Value { time: f64, before: f64, after: f64 },
+    Slope { time: f64, before: f64, after: f64 },
+}
+
+#[derive(Serialize)]
+pub struct TrackReport {
+    pub track: String,
+    pub discontinuities: Vec<Discontinuity>,
+}
+
+/// Whether every motion track and the scene as a whole are smooth and loop without a jump.
+#[derive(Serialize)]
+pub struct ContinuityReport {
+    pub tracks: Vec<TrackReport>,
+    /// Spheres of the first frame that no sphere matches just before the loop point, by name.
+    pub unmatched_at_loop: Vec<String>,
+}
+
+impl ContinuityReport {
+    pub fn new(
+        tracks: impl Iterator<Item = (String, Timeline)>,
+        frame: impl Fn(f64) -> Group,
+    ) -> Self {
+        Self {
+            tracks: tracks
+                .map(|(track, timeline)| TrackReport {
+                    track,
+                    discontinuities: discontinuities(&timeline),
+                })
+                .collect(),
+            unmatched_at_loop: unmatched(&frame(0.0), &frame(1.0 - LOOP_STEP)),
+        }
+    }
+}
+
+/// Sample `timeline` densely and at every keyframe, reporting any point where the value or the slope
+/// on one side differs from the other. The keyframe at 0.0 is the loop point, where the end of the
+/// timeline meets its start.
+pub fn discontinuities(timeline: &Timeline) -> Vec<Discontinuity> {
+    let mut times: Vec<f64> = timeline
+        .keyframe_times()
+        .filter(|time| *time < 1.0)
+        .chain((0..SAMPLES).map(|sample| sample as f64 / SAMPLES as f64))
+        .collect();
+    times.sort_by(f64::total_cmp);
+    times.dedup_by(|a, b| (*a - *b).abs() < STEP);
+
+    let mut found = vec![];
+    for time in times {
+        let before = timeline.sample(time - STEP);
+        let after = timeline.sample(time + STEP);
+        if (after - before).abs() > VALUE_TOLERANCE {
+            found.push(Discontinuity::Value {
+                time,
+                before,
+                after,
+            });
+            continue;
+        }
+        let slope_before = (before - timeline.sample(time - 2.0 * STEP)) / STEP;
+        let slope_after = (timeline.sample(time + 2.0 * STEP) - after) / STEP;
+        if (slope_after - slope_before).abs() > SLOPE_TOLERANCE {
+            found.push(Discontinuity::Slope {
+                time,
+                before: slope_before,
+                after: slope_after,
+            });
+        }
+    }
+    found
+}
+
+/// The name of each sphere of `start` that has no matching sphere in `end`, or its index if it has no
+/// name. Spheres are matched by position, size and grey regardless of order or name, since an
+/// animation may finish with identical spheres in each other's places.
+pub fn unmatched(start: &Group, end: &Group) -> Vec<String> {
+    let mut available: Vec<bool> = vec![true; end.spheres.len()];
+    start
+        .spheres
+        .iter()
+        .enumerate()
+        .filter(|(_, sphere)| {
+            let matching = end.spheres.iter().enumerate().position(|(index, other)| {
+                available[index]
+                    && other.grey_value == sphere.grey_value
+                    && (other.radius - sphere.radius).abs() < LOOP_TOLERANCE
+                    && Point3d::distance(&other.centre, &sphere.centre) < LOOP_TOLERANCE
+            });
+            match matching {
+                Some(index) => {
+                    available[index] = false;
+                    false
+                }
+                None => true,
+            }
+        })
+        .map(|(index, sphere)| sphere.label(index))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::easing::Linear;
+    use crate::timeline::Keyframe;
+    use crate::Sphere;
+    use std::collections::BTreeSet;
+
+    fn timeline(keyframes: &[(f64, f64)]) -> Timeline {
+        Timeline::new(
+            keyframes
+                .iter()
+                .map(|(time, value)| Keyframe::new(*time, *value))
+                .collect(),
+        )
+    }
+
+    fn sphere(x: f64, grey_value: u8) -> Sphere {
+        Sphere {
+            centre: Point3d { x, y: 0.0, z: 0.0 },
+            radius: 1.0,
+            grey_value,
+            name: None,
+            tags: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn eased_timeline_that_returns_to_its_start_is_continuous() {
+        let timeline = timeline(&[(0.0, 0.0), (0.25, 0.0), (0.5, 1.0), (1.0, 0.0)]);
+        assert_eq!(discontinuities(&timeline), vec![]);
+    }
+
+    #[test]
+    fn value_jump_at_the_loop_point_is_found() {
+        let timeline = timeline(&[(0.0, 0.0), (0.5, 1.0), (1.0, 1.0)]);
+        assert!(matches!(
+            discontinuities(&timeline)[..],
+            [Discontinuity::Value { time: 0.0, .. }]
+        ));
+    }
+
+    #[test]
+    fn kinks_between_linear_segments_are_found() {
+        let timeline =
+            timeline(&[(0.0, 0.0), (0.5, 1.0), (1.0, 0.0)]).with_easing(Box::new(Linear));
+        let times: Vec<f64> = discontinuities(&timeline)
+            .iter()
+            .map(|discontinuity| match discontinuity {
+                Discontinuity::Slope { time, .. } => *time,
+                Discontinuity::Value { .. } => panic!("The value is continuous."),
+            })
+            .collect();
+        assert_eq!(times, vec![0.0, 0.5]);
+    }
+
+    #[test]
+    fn spheres_are_matched_regardless_of_order() {
+        let start = Group::new(vec![sphere(-1.0, 0), sphere(1.0, 0), sphere(0.0, 255)]);
+        let end = Group::new(vec![sphere(1.0, 0), sphere(0.0, 255), sphere(-1.0, 0)]);
+        assert_eq!(unmatched(&start, &end), Vec::<String>::new());
+    }
+
+    #[test]
+    fn moved_sphere_is_unmatched() {
+        let start = Group::new(vec![sphere(0.0, 0), sphere(1.0, 255)]);
+        let end = Group::new(vec![sphere(0.0, 0), sphere(1.5, 255)]);
+        assert_eq!(unmatched(&start, &end), vec!["sphere 1"]);
+    }
+
+    #[test]
+    fn unmatched_spheres_are_reported_by_name() {
+        let mut moving = sphere(1.0, 255);
+        moving.name = Some("moving".to_string());
+        let start = Group::new(vec![sphere(0.0, 0), moving.clone()]);
+        let end = Group::new(vec![
+            sphere(0.0, 0),
+            moving
+                + Point3d {
+                    x: 0.5,
+                    y: 0.0,
+                    z: 0.0,
+                },
+        ]);
+        assert_eq!(unmatched(&start, &end), vec!["moving"]);
+    }
+}
@@ -1,618 +1,824 @@
-use base64::prelude::*;
+use common::apng::Apng;
+use common::clock::Clock;
+use common::contrast::contrast_scheme;
+use common::easing::{Easing, PositiveCos};
+use common::frame_options::{FrameFormat, FrameOptions};
+use common::gltf;
+use common::palette::{Scheme, SchemeChoice, SchemeName, UNCOLOURED};
+use common::parameters::{self, AnimationParameters, ParameterSchema};
+use common::phase::Phase;
+use common::render_options::RenderOptions;
+use common::scene_graph::Node;
+use common::sprite_sheet::SpriteSheet;
+use common::timeline::Timeline;
+use common::validation::ContinuityReport;
+use common::{
+    frame_pixels, grayscale_png, png_data_url, render, tag_set, Group, Point3d, Sphere,
+    DEFAULT_FRAMES_PER_SECOND,
+};
+use serde::{Deserialize, Serialize};
 use std::f64::consts::TAU;
-use std::fmt;
-use std::io::BufWriter;
-use std::io::Cursor;
-use std::ops::{Add, Mul, Sub};
 use wasm_bindgen::prelude::*;
-const WIDTH: u32 = 1024;
-const HEIGHT: u32 = 1024;
+const NAME: &str = "eightball";
+const SCENE_DESCRIPTION: &str = include_str!("../scenes/eightball.json");
+#[cfg(feature = "script")]
+const SCRIPT: &str = include_str!("../scripts/eightball.rhai");
+const DURATION_SECONDS: f64 = 10.0;
 
 #[wasm_bindgen]
 pub fn data_url() -> String {
-    let total_frames = 500;
-    let forward_tilt = TAU * 3.0 / 16.0;
-    let mut fake_file: Cursor<Vec<u8>> = Cursor::new(Vec::new());
-    {
-        // Start a block so that the APNG file will be closed after all of the frames have been written
-        let w = &mut BufWriter::new(&mut fake_file);
-
-        let mut encoder = png::Encoder::new(w, WIDTH, HEIGHT);
-        encoder.set_color(png::ColorType::Grayscale);
-        encoder.set_depth(png::BitDepth::Eight);
-        match encoder.set_animated(total_frames, 0) {
-            Ok(_) => (),
-            Err(error) => eprintln!("encoder.set_animated result: {error}"),
-        };
-        match encoder.set_frame_delay(2, 100) {
-            Ok(_) => (),
-            Err(error) => eprintln!("encoder.set_frame_delay result: {error}"),
-        };
-        let mut writer = encoder.write_header().unwrap();
-
-        for frame_index in 0..total_frames {
-            let animation_fraction = frame_index as f64 / total_frames as f64;
-            let eightball = Group::background(128)
-                + Group::eightball(animation_fraction)
-                    .rotate(forward_tilt, &Point3d::x_axis())
-                    .expect("The x_axis vector is not zero.")
-                    * 0.4;
-            save_apng_frame(&mut writer, WIDTH, HEIGHT, &eightball.spheres);
-        }
-    }
+    let apng = animation(&RenderOptions::default());
+    png_data_url(&apng.encode_with(&apng.optimise()))
+}
 
-    let base64_data = BASE64_STANDARD.encode(fake_file.get_ref());
-    format!("data:image/png;base64,{}", base64_data)
-}
-
-fn save_apng_frame(
-    writer: &mut png::Writer<&mut BufWriter<&mut Cursor<Vec<u8>>>>,
-    width: u32,
-    height: u32,
-    spheres: &[Sphere],
-) {
-    let mut data = vec![];
-    for y in 0..width {
-        for x in 0..height {
-            let normalised_x: f64 = x as f64 / width as f64 * 2.0 - 1.0;
-            let normalised_y: f64 = y as f64 / height as f64 * 2.0 - 1.0;
-            let result = spheres
-                .iter()
-                .filter_map(sphere_with_intersection_distance(
-                    normalised_x,
-                    normalised_y,
-                ))
-                .reduce(|current, next| {
-                    if next.distance < current.distance {
-                        next
-                    } else {
-                        current
-                    }
-                });
-            let grey = match result {
-                Some(intersection) => intersection.sphere.grey_value,
-                _ => 0,
-            };
-            data.push(grey)
-        }
-    }
-    writer.write_image_data(&data).unwrap();
+/// The animation rendered at `frames_per_second`, with each frame's delay matched to the frame rate.
+#[wasm_bindgen]
+pub fn data_url_at_frame_rate(frames_per_second: f64) -> Result<String, String> {
+    let options = RenderOptions {
+        frames_per_second,
+        ..RenderOptions::default()
+    };
+    options.validate(&eightball_phases())?;
+    let apng = animation(&options);
+    Ok(png_data_url(&apng.encode_with(&apng.optimise())))
+}
+
+/// The animation rendered with the `RenderOptions` given as JSON, for per-phase delays, a loop count
+/// or playing once.
+#[wasm_bindgen]
+pub fn data_url_with_options(options_json: &str) -> Result<String, String> {
+    let options = RenderOptions::from_json(options_json, &eightball_phases())?;
+    let apng = animation(&options);
+    Ok(png_data_url(&apng.encode_with(&apng.optimise())))
 }
 
-struct Group {
-    spheres: Vec<Sphere>,
+/// Compare the size of the animation written as 8-bit grayscale with its size after optimising the
+/// encoding, as JSON.
+#[wasm_bindgen]
+pub fn encoding_report() -> String {
+    serde_json::to_string_pretty(&animation(&RenderOptions::default()).report())
+        .expect("The report contains no maps.")
 }
 
-impl Group {
-    fn new(spheres: Vec<Sphere>) -> Self {
-        Self { spheres }
-    }
+fn default_clock() -> Clock {
+    Clock::new(DURATION_SECONDS, DEFAULT_FRAMES_PER_SECOND)
+        .expect("The duration and default frame rate are positive.")
+}
 
-    fn background(grey_value: u8) -> Self {
-        Self::new(vec![Sphere {
-            centre: Point3d {
-                x: 0.0,
-                y: 0.0,
-                z: 1000.0,
-            },
-            radius: 2.0,
-            grey_value,
-        }])
-    }
+fn animation(options: &RenderOptions<Parameters>) -> Apng {
+    let (scheme, finished_scheme) = options.colour_schemes();
+    render(
+        options,
+        DURATION_SECONDS,
+        &eightball_phases(),
+        |animation_fraction| coloured_frame(animation_fraction, &options.parameters, &scheme),
+        &coloured_frame(0.0, &options.parameters, &finished_scheme),
+    )
+}
 
-    fn eightball(animation_fraction: f64) -> Self {
-        let main_sphere = Sphere {
-            centre: Point3d {
-                x: 0.0,
-                y: 0.003125 + 2.5 * eightball_main_sphere_radial_offset(animation_fraction),
-                z: 0.0,
-            },
-            radius: 1.0,
-            grey_value: 0,
-        };
-        let white_circle_sphere = Sphere {
-            centre: Point3d {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-            },
-            radius: 0.9975,
-            grey_value: 255,
-        };
-        let eight_upper_circle_sphere = Sphere {
-            centre: Point3d {
-                x: 0.00,
-                y: -0.00263 - 3.75 * eightball_circle_radial_upper_offset(animation_fraction),
-                z: 0.0,
-            }
-            .rotate(TAU / 30.0, &Point3d::z_axis())
-            .expect("The z_axis vector is not zero.")
-            .rotate(
-                if animation_fraction > 10.0 / 11.0 {
-                    (1.0 - positive_cos((animation_fraction - 10.0 / 11.0) * 11.0 * TAU / 2.0))
-                        * TAU
-                        / 2.0
-                } else {
-                    0.0
-                },
-                &Point3d::y_axis(),
-            )
-            .expect("The y_axis vector is not zero.")
-                + Point3d {
-                    x: eightball_horizontal_upper_offset(animation_fraction),
-                    y: 0.0,
-                    z: 0.0,
-                } * 1.25,
-            radius: 0.995,
-            grey_value: 0,
-        };
-        let eight_lower_circle_sphere = Sphere {
-            centre: Point3d {
-                x: 0.0,
-                y: -0.00263 - 3.75 * eightball_circle_radial_lower_offset(animation_fraction),
-                z: 0.0,
-            }
-            .rotate(-TAU / 30.0, &Point3d::z_axis())
-            .expect("The z_axis vector is not zero.")
-            .rotate(
-                if animation_fraction > 10.0 / 11.0 {
-                    (1.0 - positive_cos((animation_fraction - 10.0 / 11.0) * 11.0 * TAU / 2.0))
-                        * TAU
-                        / 2.0
-                } else {
-                    0.0
-                },
-                &Point3d::y_axis(),
-            )
-            .expect("The y_axis vector is not zero.")
-                - Point3d {
-                    x: eightball_horizontal_lower_offset(animation_fraction),
-                    y: 0.0,
-                    z: 0.0,
-                } * 1.25,
-            radius: 0.995,
-            grey_value: 0,
-        };
-        let eight_upper_hole_sphere = Sphere {
-            centre: Point3d {
-                x: 0.0,
-                y: -0.00515 - 3.75 * eightball_hole_radial_upper_offset(animation_fraction),
-                z: 0.0,
-            }
-            .rotate(TAU / 30.0, &Point3d::z_axis())
-            .expect("The z_axis vector is not zero.")
-            .rotate(
-                if animation_fraction > 10.0 / 11.0 {
-                    (1.0 - positive_cos((animation_fraction - 10.0 / 11.0) * 11.0 * TAU / 2.0))
-                        * TAU
-                        / 2.0
-                } else {
-                    0.0
-                },
-                &Point3d::y_axis(),
-            )
-            .expect("The y_axis vector is not zero.")
-                + Point3d {
-                    x: eightball_horizontal_upper_offset(animation_fraction),
-                    y: 0.0,
-                    z: 0.0,
-                } * 1.25,
-            radius: 0.9925,
-            grey_value: 255,
-        };
-        let eight_lower_hole_sphere = Sphere {
-            centre: Point3d {
-                x: 0.0,
-                y: -0.00515 - 3.75 * eightball_hole_radial_lower_offset(animation_fraction),
-                z: 0.0,
-            }
-            .rotate(-TAU / 30.0, &Point3d::z_axis())
-            .expect("The z_axis vector is not zero.")
-            .rotate(
-                if animation_fraction > 10.0 / 11.0 {
-                    (1.0 - positive_cos((animation_fraction - 10.0 / 11.0) * 11.0 * TAU / 2.0))
-                        * TAU
-                        / 2.0
-                } else {
-                    0.0
-                },
-                &Point3d::y_axis(),
-            )
-            .expect("The y_axis vector is not zero.")
-                - Point3d {
-                    x: eightball_horizontal_lower_offset(animation_fraction),
-                    y: 0.0,
-                    z: 0.0,
-                } * 1.25,
-            radius: 0.9925,
-            grey_value: 255,
-        };
+/// Tile every `frame_step`th frame of the animation into one sprite sheet, rendering each frame at
+/// `tile_size` pixels square.
+#[wasm_bindgen]
+pub fn sprite_sheet(frame_step: u32, tile_size: u32) -> Result<SpriteSheet, String> {
+    let frame_step = frame_step.max(1);
+    let clock = default_clock();
+    let frames = (0..clock.frame_count())
+        .step_by(frame_step as usize)
+        .map(|frame_index| {
+            let animation_fraction = clock.animation_fraction(frame_index);
+            frame_pixels(tile_size, tile_size, &frame(animation_fraction).spheres)
+        });
+    SpriteSheet::new(
+        NAME,
+        frames,
+        tile_size,
+        tile_size,
+        (1000.0 * frame_step as f64 / DEFAULT_FRAMES_PER_SECOND).round() as u32,
+    )
+}
+
+/// Export the animation as a binary glTF (`.glb`) file, with one node per sphere and translation and
+/// scale keyframes for every frame.
+#[wasm_bindgen]
+pub fn glb() -> Result<Vec<u8>, String> {
+    let clock = default_clock();
+    let frames: Vec<Group> = (0..clock.frame_count())
+        .map(|frame_index| frame(clock.animation_fraction(frame_index)))
+        .collect();
+    gltf::glb(&frames, 1.0 / DEFAULT_FRAMES_PER_SECOND)
+}
 
-        Self::new(vec![
-            main_sphere,
-            white_circle_sphere,
-            eight_upper_circle_sphere,
-            eight_lower_circle_sphere,
-            eight_upper_hole_sphere,
-            eight_lower_hole_sphere,
-        ])
-    }
+/// The description of the animation in the JSON scene format that `scene_animation_data_url` reads.
+#[wasm_bindgen]
+pub fn scene_description() -> String {
+    SCENE_DESCRIPTION.to_string()
+}
 
-    fn rotate(&self, angle: f64, axis: &Point3d) -> Option<Self> {
-        match axis {
-            x if *x == Point3d::origin() => None,
-            _ => Some(Self {
-                spheres: self
-                    .spheres
-                    .iter()
-                    .filter_map(|sphere| sphere.rotate(angle, axis))
-                    .collect(),
-            }),
-        }
-    }
+/// Take apart the assembled group given as JSON, in the form `scene_json` gives, and put it back
+/// together as the `ExplodeOptions` given as JSON describe, rendered in the same way as the built-in
+/// animation at the default frame rate.
+#[wasm_bindgen]
+pub fn explode_data_url(group_json: &str, options_json: &str) -> Result<String, String> {
+    common::explode_data_url(group_json, options_json)
 }
 
-impl Add for Group {
-    type Output = Self;
+/// Check the movement of every sphere taken apart by `explode_data_url` for jumps in value or slope,
+/// and check that it loops back to the assembled group, as JSON.
+#[wasm_bindgen]
+pub fn explode_continuity_report(group_json: &str, options_json: &str) -> Result<String, String> {
+    common::explode_continuity_report(group_json, options_json)
+}
 
-    fn add(self, rhs: Self) -> Self::Output {
-        let mut spheres = self.spheres;
-        spheres.extend(rhs.spheres);
-        Self { spheres }
-    }
+/// Morph between two groups given as JSON, in the form `scene_json` gives, such as the assembled
+/// beachball and the assembled starball from their own modules. The animation holds the first group,
+/// morphs into the second, holds it and morphs back, rendered in the same way as the built-in
+/// animation at the default frame rate. See `common::morph::Morph`.
+#[wasm_bindgen]
+pub fn morph_data_url(from_json: &str, to_json: &str) -> Result<String, String> {
+    common::morph_data_url(from_json, to_json)
 }
 
-impl Add<Point3d> for Group {
-    type Output = Self;
+/// A novelty ball built at random from `seed`, coming apart and back together, rendered in the same
+/// way as the built-in animation at the default frame rate. See `common::random_ball::RandomBall`.
+#[wasm_bindgen]
+pub fn random_ball_data_url(seed: u32) -> String {
+    common::random_ball_data_url(seed)
+}
 
-    fn add(self, rhs: Point3d) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() + rhs.clone())
-                .collect(),
-        }
-    }
+/// The random ball built from `seed` at `animation_fraction` as JSON, in the same form as
+/// `scene_json`, for keeping as a regression fixture.
+#[wasm_bindgen]
+pub fn random_ball_json(seed: u32, animation_fraction: f64) -> String {
+    common::random_ball_json(seed, animation_fraction)
 }
 
-impl Sub<Point3d> for Group {
-    type Output = Self;
+/// Load an animation described in the JSON scene format and render it in the same way as the
+/// built-in animation, at the default frame rate.
+#[wasm_bindgen]
+pub fn scene_animation_data_url(json: &str) -> Result<String, String> {
+    common::scene_animation_data_url(json, Parameters::DEFAULT_SCHEME)
+}
 
-    fn sub(self, rhs: Point3d) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() - rhs.clone())
-                .collect(),
-        }
-    }
+/// The animation written as a Rhai script, in the form that `script_animation_data_url` reads.
+#[cfg(feature = "script")]
+#[wasm_bindgen]
+pub fn script() -> String {
+    SCRIPT.to_string()
 }
 
-impl Mul<f64> for Group {
-    type Output = Self;
+/// Run an animation written as a Rhai script and render it in the same way as the built-in animation,
+/// at the default frame rate. See `common::script::Script` for what a script can use.
+#[cfg(feature = "script")]
+#[wasm_bindgen]
+pub fn script_animation_data_url(source: &str) -> Result<String, String> {
+    common::script_animation_data_url(source)
+}
 
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() * rhs)
-                .collect(),
-        }
-    }
+/// Render the named animation at a single `animation_fraction`, without rendering the rest of it, as
+/// the PNG file or raw grey values that `options_json` asks for.
+#[wasm_bindgen]
+pub fn render_frame(
+    name: &str,
+    animation_fraction: f64,
+    options_json: &str,
+) -> Result<Vec<u8>, String> {
+    if name != NAME {
+        return Err(format!("This module renders the {NAME}, not \"{name}\"."));
+    }
+    if !animation_fraction.is_finite() {
+        return Err("The animation fraction is a finite number.".to_string());
+    }
+    let options = FrameOptions::<Parameters>::from_json(options_json)?;
+    let pixels = frame_pixels(
+        options.width,
+        options.height,
+        &coloured_frame(
+            animation_fraction,
+            &options.parameters,
+            &options.colour_scheme.scheme(&options.parameters),
+        )
+        .spheres,
+    );
+    Ok(match options.format {
+        FrameFormat::Png => grayscale_png(options.width, options.height, &pixels),
+        FrameFormat::Raw => pixels,
+    })
 }
 
-#[derive(Clone)]
-struct Sphere {
-    centre: Point3d,
-    radius: f64,
-    grey_value: u8,
+/// The scene at `animation_fraction` as JSON, which `scene_image_data_url` reads back exactly.
+#[wasm_bindgen]
+pub fn scene_json(animation_fraction: f64) -> String {
+    frame(animation_fraction).to_json()
 }
 
-impl Sphere {
-    fn rotate(&self, angle: f64, axis: &Point3d) -> Option<Self> {
-        Some(Self {
-            centre: self.centre.rotate(angle, axis)?,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        })
-    }
+/// Render a scene exported by `scene_json` as a still PNG.
+#[wasm_bindgen]
+pub fn scene_image_data_url(json: &str) -> Result<String, String> {
+    common::scene_image_data_url(json)
 }
 
-impl fmt::Display for Sphere {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "[{}, {}, {}],",
-            self.centre, self.radius, self.grey_value
-        )
-    }
+/// The named phases of the animation in order, with their start and end fractions and captions, as
+/// JSON.
+#[wasm_bindgen]
+pub fn phases_json() -> String {
+    serde_json::to_string(&eightball_phases()).expect("A phase contains no maps.")
 }
 
-impl Mul<f64> for Sphere {
-    type Output = Self;
+/// The standard and explanatory colour schemes as JSON, to start a custom scheme from.
+#[wasm_bindgen]
+pub fn colour_schemes_json() -> String {
+    let scheme = |name| SchemeChoice::Named(name).scheme(&Parameters::default());
+    serde_json::json!({
+        "standard": scheme(SchemeName::Standard),
+        "explanatory": scheme(SchemeName::Explanatory),
+    })
+    .to_string()
+}
 
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self {
-            centre: self.centre * rhs,
-            radius: self.radius * rhs,
-            grey_value: self.grey_value,
-        }
-    }
+/// The parameters that `RenderOptions` and `FrameOptions` accept to vary the ball, with their
+/// descriptions, types, defaults and ranges, as JSON.
+#[wasm_bindgen]
+pub fn parameter_schema_json() -> String {
+    parameters::schema_json::<Parameters>()
 }
 
-impl Add<Point3d> for Sphere {
-    type Output = Self;
+/// A colour scheme as JSON that gives neighbouring spheres of the assembled eightball greys that differ as
+/// much as possible, to pass as a custom `colour_scheme`. See `common::contrast::contrast_scheme`.
+#[wasm_bindgen]
+pub fn contrast_scheme_json() -> String {
+    serde_json::to_string(&contrast_scheme(&coloured_frame(
+        0.0,
+        &Parameters::default(),
+        &Scheme::new(&[]),
+    )))
+    .expect("A scheme contains no maps.")
+}
 
-    fn add(self, rhs: Point3d) -> Self::Output {
-        Self {
-            centre: self.centre + rhs,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        }
-    }
+/// The same as `contrast_scheme_json` for an animation described in the JSON scene format, which is
+/// assembled in its first frame.
+#[wasm_bindgen]
+pub fn scene_contrast_scheme_json(json: &str) -> Result<String, String> {
+    common::scene_contrast_scheme_json(json)
 }
 
-impl Sub<Point3d> for Sphere {
-    type Output = Self;
+/// Check every motion track for jumps in value or slope, and check that the end of the animation leads
+/// back into its first frame, as JSON.
+#[wasm_bindgen]
+pub fn continuity_report() -> String {
+    serde_json::to_string_pretty(&continuity()).expect("The report contains no maps.")
+}
 
-    fn sub(self, rhs: Point3d) -> Self::Output {
-        Self {
-            centre: self.centre - rhs,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        }
-    }
+fn continuity() -> ContinuityReport {
+    ContinuityReport::new(
+        EightballTrack::ALL
+            .iter()
+            .map(|track| (format!("{track:?}"), eightball_timeline(*track))),
+        frame,
+    )
 }
 
-#[derive(Clone, PartialEq)]
-struct Point3d {
-    x: f64,
-    y: f64,
-    z: f64,
+/// Sample a path described in JSON at `samples` evenly spaced steps of progress from its start to its
+/// end inclusive, giving the points as JSON, for previewing the path a sphere would follow.
+#[wasm_bindgen]
+pub fn sample_path(json: &str, samples: u32) -> Result<String, String> {
+    common::sample_path(json, samples)
 }
 
-impl Point3d {
-    fn normalise(&self) -> Option<Self> {
-        let distance = Self::distance(self, &Self::origin());
-        if distance == 0.0 {
-            return None;
-        }
-        let x = self.x / distance;
-        let y = self.y / distance;
-        let z = self.z / distance;
-        Some(Self { x, y, z })
-    }
+/// Sample the named easing at `samples` evenly spaced points from 0.0 to 1.0 inclusive, for plotting
+/// its curve.
+#[wasm_bindgen]
+pub fn easing_curve(name: &str, samples: u32) -> Result<Vec<f64>, String> {
+    common::easing_curve(name, samples)
+}
 
-    fn distance(a: &Self, b: &Self) -> f64 {
-        ((a.x - b.x).powf(2.0) + (a.y - b.y).powf(2.0) + (a.z - b.z).powf(2.0)).powf(0.5)
-    }
+fn frame(animation_fraction: f64) -> Group {
+    let parameters = Parameters::default();
+    coloured_frame(
+        animation_fraction,
+        &parameters,
+        &SchemeChoice::default_for::<Parameters>().scheme(&parameters),
+    )
+}
 
-    fn origin() -> Self {
-        Self {
+fn coloured_frame(animation_fraction: f64, parameters: &Parameters, scheme: &Scheme) -> Group {
+    let forward_tilt = TAU * 3.0 / 16.0;
+    scheme.colour(
+        background()
+            + eightball(animation_fraction, parameters)
+                .rotate(forward_tilt, &Point3d::x_axis())
+                .expect("The x_axis vector is not zero.")
+                .scale(0.4)
+                .flatten(),
+    )
+}
+
+// The eightball itself, before it is tilted towards the viewer
+fn eightball(animation_fraction: f64, parameters: &Parameters) -> Node {
+    let track = |track: EightballTrack| eightball_timeline(track).sample(animation_fraction);
+    let main_sphere = Sphere {
+        centre: Point3d {
+            x: 0.0,
+            y: parameters.white_circle_offset + 2.5 * track(EightballTrack::MainSphereRadial),
+            z: 0.0,
+        },
+        radius: 1.0,
+        grey_value: UNCOLOURED,
+        name: Some("main sphere".to_string()),
+        tags: tag_set(&["ball"]),
+    };
+    let white_circle_sphere = Sphere {
+        centre: Point3d {
             x: 0.0,
             y: 0.0,
             z: 0.0,
-        }
-    }
-
-    // Based on https://en.wikipedia.org/wiki/Rotation_matrix#Rotation_matrix_from_axis_and_angle
-    fn rotate(&self, angle: f64, axis: &Self) -> Option<Self> {
-        let u = axis.normalise()?;
-        let s = angle.sin();
-        let c = angle.cos();
-        let d = 1.0 - c;
-        Some(Self {
-            x: self.x * (c + u.x.powf(2.0) * d)
-                + self.y * (u.x * u.y * d - u.z * s)
-                + self.z * (u.x * u.z * d + u.y * s),
-            y: self.x * (u.y * u.x * d + u.z * s)
-                + self.y * (c + u.y.powf(2.0) * d)
-                + self.z * (u.y * u.z * d - u.x * s),
-            z: self.x * (u.z * u.x * d - u.y * s)
-                + self.y * (u.z * u.y * d + u.x * s)
-                + self.z * (c + u.z.powf(2.0) * d),
-        })
-    }
-
-    fn x_axis() -> Self {
-        Self {
-            x: 1.0,
+        },
+        radius: 0.9975,
+        grey_value: UNCOLOURED,
+        name: Some("white circle sphere".to_string()),
+        tags: tag_set(&["white-circle"]),
+    };
+    // Each half of the 8 is a black circle sphere with a white hole sphere inside it, each tilted to
+    // its side of the glyph and moved sideways with its half
+    let eight_half = |side: &str,
+                      circle_radial: EightballTrack,
+                      hole_radial: EightballTrack,
+                      tilt: f64,
+                      offset: Point3d| {
+        let place = |sphere: Sphere| {
+            Node::new(vec![sphere.into()])
+                .rotate(tilt, &Point3d::z_axis())
+                .expect("The z_axis vector is not zero.")
+                .translate(offset.clone())
+        };
+        let circle = place(Sphere {
+            centre: Point3d {
+                x: 0.0,
+                y: -parameters.eight_circle_offset - parameters.eight_lift * track(circle_radial),
+                z: 0.0,
+            },
+            radius: 0.995,
+            grey_value: UNCOLOURED,
+            name: Some(format!("eight {side} circle sphere")),
+            tags: tag_set(&["eight", "eight-circle"]),
+        });
+        let hole = place(Sphere {
+            centre: Point3d {
+                x: 0.0,
+                y: -parameters.eight_hole_offset - parameters.eight_lift * track(hole_radial),
+                z: 0.0,
+            },
+            radius: 0.9925,
+            grey_value: UNCOLOURED,
+            name: Some(format!("eight {side} hole sphere")),
+            tags: tag_set(&["eight", "eight-hole"]),
+        });
+        (circle, hole)
+    };
+    let (upper_circle, upper_hole) = eight_half(
+        "upper",
+        EightballTrack::UpperCircleRadial,
+        EightballTrack::UpperHoleRadial,
+        TAU / 30.0,
+        Point3d {
+            x: track(EightballTrack::UpperHorizontal),
             y: 0.0,
             z: 0.0,
-        }
-    }
-
-    fn y_axis() -> Self {
-        Self {
-            x: 0.0,
-            y: 1.0,
+        } * 1.25,
+    );
+    let (lower_circle, lower_hole) = eight_half(
+        "lower",
+        EightballTrack::LowerCircleRadial,
+        EightballTrack::LowerHoleRadial,
+        -TAU / 30.0,
+        Point3d {
+            x: -track(EightballTrack::LowerHorizontal),
+            y: 0.0,
             z: 0.0,
-        }
-    }
-
-    fn z_axis() -> Self {
-        Self {
+        } * 1.25,
+    );
+    // The 8 only flips once both halves are back in place, so flipping it as a unit after their
+    // sideways offsets gives the same spheres as flipping each sphere before its offset. The spheres
+    // are listed circles first, then holes, each upper then lower.
+    let eight = Node::new(vec![
+        upper_circle.into(),
+        lower_circle.into(),
+        upper_hole.into(),
+        lower_hole.into(),
+    ])
+    .rotate(track(EightballTrack::Flip) * TAU / 2.0, &Point3d::y_axis())
+    .expect("The y_axis vector is not zero.");
+
+    Node::new(vec![
+        main_sphere.into(),
+        white_circle_sphere.into(),
+        eight.into(),
+    ])
+}
+
+fn background() -> Group {
+    Group::new(vec![Sphere {
+        centre: Point3d {
             x: 0.0,
             y: 0.0,
-            z: 1.0,
-        }
-    }
-}
-
-impl Add for Point3d {
-    type Output = Self;
-
-    fn add(self, rhs: Self) -> Self::Output {
+            z: 1000.0,
+        },
+        radius: 2.0,
+        grey_value: UNCOLOURED,
+        name: Some("background".to_string()),
+        tags: tag_set(&["background"]),
+    }])
+}
+
+#[derive(Clone, Copy, Debug)]
+enum EightballTrack {
+    MainSphereRadial,
+    UpperCircleRadial,
+    LowerCircleRadial,
+    UpperHoleRadial,
+    LowerHoleRadial,
+    UpperHorizontal,
+    LowerHorizontal,
+    Flip,
+}
+
+// One row per keyframe: the fraction of the way through the animation, followed by the value of each
+// track in the order they are listed in `EightballTrack`. The flip is measured in half turns.
+const EIGHTBALL_KEYFRAMES: [(f64, [f64; 8]); 12] = [
+    (0.0, [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+    (1.0 / 11.0, [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+    (2.0 / 11.0, [1.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]),
+    (3.0 / 11.0, [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0]),
+    (4.0 / 11.0, [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0]),
+    (5.0 / 11.0, [1.0, 0.5, 0.5, 1.0, 1.0, 1.0, 1.0, 0.0]),
+    (6.0 / 11.0, [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0]),
+    (7.0 / 11.0, [1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 1.0, 0.0]),
+    (8.0 / 11.0, [1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0]),
+    (9.0 / 11.0, [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+    (10.0 / 11.0, [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+    (1.0, [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0]),
+];
+
+impl EightballTrack {
+    const ALL: [Self; 8] = [
+        Self::MainSphereRadial,
+        Self::UpperCircleRadial,
+        Self::LowerCircleRadial,
+        Self::UpperHoleRadial,
+        Self::LowerHoleRadial,
+        Self::UpperHorizontal,
+        Self::LowerHorizontal,
+        Self::Flip,
+    ];
+
+    fn easing(self) -> Box<dyn Easing> {
+        Box::new(PositiveCos)
+    }
+}
+
+fn eightball_timeline(track: EightballTrack) -> Timeline {
+    Timeline::from_table(&EIGHTBALL_KEYFRAMES, track as usize).with_easing(track.easing())
+}
+
+// The name and caption of each phase, one for each step between rows of `EIGHTBALL_KEYFRAMES`
+const EIGHTBALL_PHASES: [(&str, &str); 11] = [
+    (
+        "Ball lifts",
+        "The black ball lifts away, showing the white sphere that makes the white circle.",
+    ),
+    (
+        "Upper circle out",
+        "Upper circle of the 8 lifts out, with the white sphere that makes its hole.",
+    ),
+    (
+        "Lower circle out",
+        "Lower circle of the 8 lifts out with its hole, while the upper circle slides aside.",
+    ),
+    (
+        "Lower circle aside",
+        "Lower circle of the 8 slides aside with its hole.",
+    ),
+    (
+        "Circles in",
+        "The two black circle spheres move halfway back in without their holes.",
+    ),
+    (
+        "Circles out",
+        "The two black circle spheres move back out to their holes.",
+    ),
+    (
+        "Upper circle back",
+        "Upper circle of the 8 slides back into line with its hole.",
+    ),
+    (
+        "Upper circle in",
+        "Upper circle of the 8 moves back in with its hole, while the lower circle slides back into line.",
+    ),
+    (
+        "Lower circle in",
+        "Lower circle of the 8 moves back in with its hole, completing the 8.",
+    ),
+    (
+        "Ball returns",
+        "The black ball moves back into place around the white circle.",
+    ),
+    (
+        "Flip",
+        "The 8 turns half a turn about the vertical axis, looking just as it began.",
+    ),
+];
+
+fn eightball_phases() -> Vec<Phase> {
+    Phase::between_keyframes(&EIGHTBALL_KEYFRAMES, &EIGHTBALL_PHASES)
+}
+
+const STANDARD_SCHEME: &[(&str, u8)] = &[
+    ("background", 128),
+    ("ball", 0),
+    ("white-circle", 255),
+    ("eight-circle", 0),
+    ("eight-hole", 255),
+];
+
+// Lighten the circles of the 8 and darken their holes, so that they stand out from the ball and the
+// white circle they sit in
+const EXPLANATORY_SCHEME: &[(&str, u8)] = &[("eight-circle", 64), ("eight-hole", 192)];
+
+/// The parameters that vary the eightball, read from JSON and described in `PARAMETER_SCHEMA`.
+/// Every field is optional.
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Parameters {
+    white_circle_offset: f64,
+    eight_circle_offset: f64,
+    eight_hole_offset: f64,
+    eight_lift: f64,
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
         Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-            z: self.z + rhs.z,
+            white_circle_offset: 0.003125,
+            eight_circle_offset: 0.00263,
+            eight_hole_offset: 0.00515,
+            eight_lift: 3.75,
         }
     }
 }
 
-impl Sub for Point3d {
-    type Output = Self;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        self + Self {
-            x: -rhs.x,
-            y: -rhs.y,
-            z: -rhs.z,
+const PARAMETER_SCHEMA: &[ParameterSchema] = &[
+    ParameterSchema {
+        name: "white_circle_offset",
+        description: "How far the black ball sits off centre, uncovering the white circle sphere, which sets how large the white circle is.",
+        min: 0.0,
+        max: 0.01,
+    },
+    ParameterSchema {
+        name: "eight_circle_offset",
+        description: "How far each circle sphere of the 8 sits off centre, which sets how large the circles are.",
+        min: 0.0,
+        max: 0.01,
+    },
+    ParameterSchema {
+        name: "eight_hole_offset",
+        description: "How far each hole sphere of the 8 sits off centre, which sets how large the holes are.",
+        min: 0.0,
+        max: 0.01,
+    },
+    ParameterSchema {
+        name: "eight_lift",
+        description: "How far the circles of the 8 and their holes lift out from the ball.",
+        min: 1.0,
+        max: 6.0,
+    },
+];
+
+impl AnimationParameters for Parameters {
+    const SCHEMA: &'static [ParameterSchema] = PARAMETER_SCHEMA;
+    // The animation shows the ball in its finished colours unless another scheme is chosen
+    const DEFAULT_SCHEME: SchemeName = SchemeName::Standard;
+
+    fn standard_scheme(&self) -> Scheme {
+        Scheme::new(STANDARD_SCHEME)
+    }
+
+    fn explanatory_scheme(&self) -> Scheme {
+        Scheme::new(EXPLANATORY_SCHEME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::animation_checks::AnimationChecks;
+    use common::easing::positive_cos;
+    use common::validation::Discontinuity;
+
+    #[test]
+    fn only_the_flip_jumps_and_only_at_the_loop_point() {
+        // The flip finishes half a turn about the vertical axis from where it started. Half a turn
+        // carries the upper circle and hole spheres onto the lower ones and back, so the glyph looks
+        // the same and the jump back to no turn at all does not show.
+        for track in continuity().tracks {
+            match track.track.as_str() {
+                "Flip" => assert!(
+                    matches!(
+                        track.discontinuities[..],
+                        [Discontinuity::Value { time: 0.0, .. }]
+                    ),
+                    "Flip jumps only at the loop point."
+                ),
+                _ => assert_eq!(
+                    track.discontinuities,
+                    vec![],
+                    "{} is continuous.",
+                    track.track
+                ),
+            }
         }
     }
-}
 
-impl Mul<f64> for Point3d {
-    type Output = Self;
-
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self {
-            x: self.x * rhs,
-            y: self.y * rhs,
-            z: self.z * rhs,
-        }
+    #[test]
+    fn end_of_the_animation_leads_into_the_first_frame() {
+        assert_eq!(continuity().unmatched_at_loop, Vec::<String>::new());
     }
-}
 
-impl fmt::Display for Point3d {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}, {}, {}", self.x, self.y, self.z)
+    // The piecewise functions the keyframes replaced, each giving a track's value at fraction `a` of
+    // the animation
+    fn eightball_main_sphere_radial_offset(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => eightball_main_sphere_radial_offset(a + 1.0),
+            x if x < 1.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 9.0 / 11.0 => 1.0,
+            x if x < 10.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 1.0 => 0.0,
+            _ => eightball_main_sphere_radial_offset(a % 1.0),
+        }
     }
-}
 
-struct Point2d {
-    x: f64,
-    y: f64,
-}
-
-impl Point2d {
-    fn distance(a: Self, b: Self) -> f64 {
-        ((a.x - b.x).powf(2.0) + (a.y - b.y).powf(2.0)).powf(0.5)
+    fn eightball_circle_radial_upper_offset(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => eightball_circle_radial_upper_offset(a + 1.0),
+            x if x < 1.0 / 11.0 => 0.0,
+            x if x < 2.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 4.0 / 11.0 => 1.0,
+            x if x < 6.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU) * 0.5 + 0.5,
+            x if x < 7.0 / 11.0 => 1.0,
+            x if x < 8.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 1.0 => 0.0,
+            _ => eightball_circle_radial_upper_offset(a % 1.0),
+        }
     }
-}
-
-struct Intersection {
-    sphere: Sphere,
-    distance: f64,
-}
 
-fn sphere_with_intersection_distance(
-    x: f64,
-    y: f64,
-) -> impl FnMut(&Sphere) -> Option<Intersection> {
-    move |sphere| {
-        let distance = Point2d::distance(
-            Point2d { x, y },
-            Point2d {
-                x: sphere.centre.x as f64,
-                y: sphere.centre.y as f64,
-            },
-        );
-        let radius = sphere.radius as f64;
-        if distance < radius {
-            Some(Intersection {
-                sphere: sphere.clone(),
-                distance: 1000.0 + sphere.centre.z as f64
-                    - (radius.powf(2.0) - distance.powf(2.0)).powf(0.5)
-                    - 1000.0,
-            })
-        } else {
-            None
+    fn eightball_circle_radial_lower_offset(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => eightball_circle_radial_lower_offset(a + 1.0),
+            x if x < 2.0 / 11.0 => 0.0,
+            x if x < 3.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 4.0 / 11.0 => 1.0,
+            x if x < 6.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU) * 0.5 + 0.5,
+            x if x < 8.0 / 11.0 => 1.0,
+            x if x < 9.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 1.0 => 0.0,
+            _ => eightball_circle_radial_lower_offset(a % 1.0),
         }
     }
-}
 
-fn eightball_main_sphere_radial_offset(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => eightball_main_sphere_radial_offset(a + 1.0),
-        x if x < 1.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 9.0 / 11.0 => 1.0,
-        x if x < 10.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 1.0 => 0.0,
-        _ => eightball_main_sphere_radial_offset(a % 1.0),
+    fn eightball_hole_radial_upper_offset(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => eightball_hole_radial_upper_offset(a + 1.0),
+            x if x < 1.0 / 11.0 => 0.0,
+            x if x < 2.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 7.0 / 11.0 => 1.0,
+            x if x < 8.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 1.0 => 0.0,
+            _ => eightball_hole_radial_upper_offset(a % 1.0),
+        }
     }
-}
 
-fn eightball_circle_radial_upper_offset(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => eightball_circle_radial_upper_offset(a + 1.0),
-        x if x < 1.0 / 11.0 => 0.0,
-        x if x < 2.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 4.0 / 11.0 => 1.0,
-        x if x < 6.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU) * 0.5 + 0.5,
-        x if x < 7.0 / 11.0 => 1.0,
-        x if x < 8.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 1.0 => 0.0,
-        _ => eightball_circle_radial_upper_offset(a % 1.0),
+    fn eightball_hole_radial_lower_offset(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => eightball_hole_radial_lower_offset(a + 1.0),
+            x if x < 2.0 / 11.0 => 0.0,
+            x if x < 3.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 8.0 / 11.0 => 1.0,
+            x if x < 9.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 1.0 => 0.0,
+            _ => eightball_hole_radial_lower_offset(a % 1.0),
+        }
     }
-}
 
-fn eightball_circle_radial_lower_offset(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => eightball_circle_radial_lower_offset(a + 1.0),
-        x if x < 2.0 / 11.0 => 0.0,
-        x if x < 3.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 4.0 / 11.0 => 1.0,
-        x if x < 6.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU) * 0.5 + 0.5,
-        x if x < 8.0 / 11.0 => 1.0,
-        x if x < 9.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 1.0 => 0.0,
-        _ => eightball_circle_radial_lower_offset(a % 1.0),
+    fn eightball_horizontal_upper_offset(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => eightball_horizontal_upper_offset(a + 1.0),
+            x if x < 2.0 / 11.0 => 0.0,
+            x if x < 3.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 6.0 / 11.0 => 1.0,
+            x if x < 7.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 1.0 => 0.0,
+            _ => eightball_horizontal_upper_offset(a % 1.0),
+        }
     }
-}
 
-fn eightball_hole_radial_upper_offset(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => eightball_hole_radial_upper_offset(a + 1.0),
-        x if x < 1.0 / 11.0 => 0.0,
-        x if x < 2.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 7.0 / 11.0 => 1.0,
-        x if x < 8.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 1.0 => 0.0,
-        _ => eightball_hole_radial_upper_offset(a % 1.0),
+    fn eightball_horizontal_lower_offset(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => eightball_horizontal_lower_offset(a + 1.0),
+            x if x < 3.0 / 11.0 => 0.0,
+            x if x < 4.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 7.0 / 11.0 => 1.0,
+            x if x < 8.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
+            x if x < 1.0 => 0.0,
+            _ => eightball_horizontal_lower_offset(a % 1.0),
+        }
     }
-}
 
-fn eightball_hole_radial_lower_offset(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => eightball_hole_radial_lower_offset(a + 1.0),
-        x if x < 2.0 / 11.0 => 0.0,
-        x if x < 3.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 8.0 / 11.0 => 1.0,
-        x if x < 9.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 1.0 => 0.0,
-        _ => eightball_hole_radial_lower_offset(a % 1.0),
+    // The flip was written inline as an angle in radians, for fractions from 0.0 up to 1.0
+    fn eightball_flip(a: f64) -> f64 {
+        let a = a.rem_euclid(1.0);
+        let angle = if a > 10.0 / 11.0 {
+            (1.0 - positive_cos((a - 10.0 / 11.0) * 11.0 * TAU / 2.0)) * TAU / 2.0
+        } else {
+            0.0
+        };
+        angle / (TAU / 2.0)
+    }
+
+    #[test]
+    fn the_keyframes_match_the_original_piecewise_functions() {
+        for step in -22..=242 {
+            let a = step as f64 / 220.0;
+            for (track, original) in [
+                (
+                    EightballTrack::MainSphereRadial,
+                    eightball_main_sphere_radial_offset as fn(f64) -> f64,
+                ),
+                (
+                    EightballTrack::UpperCircleRadial,
+                    eightball_circle_radial_upper_offset,
+                ),
+                (
+                    EightballTrack::LowerCircleRadial,
+                    eightball_circle_radial_lower_offset,
+                ),
+                (
+                    EightballTrack::UpperHoleRadial,
+                    eightball_hole_radial_upper_offset,
+                ),
+                (
+                    EightballTrack::LowerHoleRadial,
+                    eightball_hole_radial_lower_offset,
+                ),
+                (
+                    EightballTrack::UpperHorizontal,
+                    eightball_horizontal_upper_offset,
+                ),
+                (
+                    EightballTrack::LowerHorizontal,
+                    eightball_horizontal_lower_offset,
+                ),
+                (EightballTrack::Flip, eightball_flip),
+            ] {
+                let sampled = eightball_timeline(track).sample(a);
+                assert!(
+                    (sampled - original(a)).abs() < 1e-9,
+                    "{track:?} at {a} is {sampled} rather than {}.",
+                    original(a)
+                );
+            }
+        }
     }
-}
 
-fn eightball_horizontal_upper_offset(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => eightball_horizontal_upper_offset(a + 1.0),
-        x if x < 2.0 / 11.0 => 0.0,
-        x if x < 3.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 6.0 / 11.0 => 1.0,
-        x if x < 7.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 1.0 => 0.0,
-        _ => eightball_horizontal_upper_offset(a % 1.0),
+    #[test]
+    fn the_spheres_are_listed_circles_before_holes() {
+        let names: Vec<String> = frame(0.5)
+            .spheres
+            .into_iter()
+            .filter_map(|sphere| sphere.name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "background",
+                "main sphere",
+                "white circle sphere",
+                "eight upper circle sphere",
+                "eight lower circle sphere",
+                "eight upper hole sphere",
+                "eight lower hole sphere",
+            ]
+        );
     }
-}
 
-fn eightball_horizontal_lower_offset(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => eightball_horizontal_lower_offset(a + 1.0),
-        x if x < 3.0 / 11.0 => 0.0,
-        x if x < 4.0 / 11.0 => positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 7.0 / 11.0 => 1.0,
-        x if x < 8.0 / 11.0 => 1.0 - positive_cos(a * 11.0 / 2.0 * TAU),
-        x if x < 1.0 => 0.0,
-        _ => eightball_horizontal_lower_offset(a % 1.0),
+    #[test]
+    fn the_checks_shared_by_every_animation_pass() {
+        AnimationChecks {
+            name: NAME,
+            duration_seconds: DURATION_SECONDS,
+            scene_description: SCENE_DESCRIPTION,
+            #[cfg(feature = "script")]
+            script: SCRIPT,
+            coloured_frame,
+            render_frame,
+        }
+        .run();
     }
 }
-
-fn positive_cos(a: f64) -> f64 {
-    (a.cos() + 1.0) * 0.5
-}
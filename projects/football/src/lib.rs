@@ -1,508 +1,682 @@
-use base64::prelude::*;
+use common::apng::Apng;
+use common::clock::Clock;
+use common::contrast::contrast_scheme;
+use common::easing::{Easing, PositiveCos};
+use common::frame_options::{FrameFormat, FrameOptions};
+use common::gltf;
+use common::palette::{Scheme, SchemeChoice, SchemeName, UNCOLOURED};
+use common::parameters::{self, AnimationParameters, ParameterSchema};
+use common::phase::Phase;
+use common::render_options::RenderOptions;
+use common::scene_graph::Node;
+use common::sprite_sheet::SpriteSheet;
+use common::timeline::Timeline;
+use common::validation::ContinuityReport;
+use common::{
+    frame_pixels, grayscale_png, png_data_url, render_with_progress, tag_set, Group, Point3d,
+    Sphere, DEFAULT_FRAMES_PER_SECOND,
+};
+use serde::{Deserialize, Serialize};
 use std::f64::consts::TAU;
-use std::fmt;
-use std::io::BufWriter;
-use std::io::Cursor;
-use std::ops::{Add, Mul, Sub};
 use wasm_bindgen::prelude::*;
-const WIDTH: u32 = 1024;
-const HEIGHT: u32 = 1024;
+const NAME: &str = "football";
+const SCENE_DESCRIPTION: &str = include_str!("../scenes/football.json");
+#[cfg(feature = "script")]
+const SCRIPT: &str = include_str!("../scripts/football.rhai");
+const DURATION_SECONDS: f64 = 10.0;
 const PHI: f64 = 1.618_033_988_749_895;
 const PHI_SQUARED: f64 = PHI * PHI;
 
 #[wasm_bindgen]
 pub fn data_url() -> String {
-    let total_frames = 500;
-    let mut fake_file: Cursor<Vec<u8>> = Cursor::new(Vec::new());
-    {
-        // Start a block so that the APNG file will be closed after all of the frames have been written
-        let w = &mut BufWriter::new(&mut fake_file);
-
-        let mut encoder = png::Encoder::new(w, WIDTH, HEIGHT);
-        encoder.set_color(png::ColorType::Grayscale);
-        encoder.set_depth(png::BitDepth::Eight);
-        match encoder.set_animated(total_frames, 0) {
-            Ok(_) => (),
-            Err(error) => eprintln!("encoder.set_animated result: {error}"),
-        };
-        match encoder.set_frame_delay(2, 100) {
-            Ok(_) => (),
-            Err(error) => eprintln!("encoder.set_frame_delay result: {error}"),
-        };
-        let mut writer = encoder.write_header().unwrap();
-        for frame_index in 0..total_frames {
-            eprintln!("Football frame {frame_index:03} of {total_frames}");
-            let animation_fraction = frame_index as f64 / total_frames as f64;
-            let football = Group::football(animation_fraction, ColourScheme::Explanatory)
-                .rotate(TAU / 8.0, &Point3d::x_axis())
-                .expect("The x_axis vector is not zero.")
-                * 0.25;
-            save_apng_frame(&mut writer, WIDTH, HEIGHT, &football.spheres);
-        }
-    }
-
-    let base64_data = BASE64_STANDARD.encode(fake_file.get_ref());
-    format!("data:image/png;base64,{}", base64_data)
-}
-
-fn save_apng_frame(
-    writer: &mut png::Writer<&mut BufWriter<&mut Cursor<Vec<u8>>>>,
-    width: u32,
-    height: u32,
-    spheres: &[Sphere],
-) {
-    let mut data = vec![];
-    for y in 0..width {
-        for x in 0..height {
-            let normalised_x: f64 = x as f64 / width as f64 * 2.0 - 1.0;
-            let normalised_y: f64 = y as f64 / height as f64 * 2.0 - 1.0;
-            let result = spheres
-                .iter()
-                .filter_map(sphere_with_intersection_distance(
-                    normalised_x,
-                    normalised_y,
-                ))
-                .reduce(|current, next| {
-                    if next.distance < current.distance {
-                        next
-                    } else {
-                        current
-                    }
-                });
-            let grey = match result {
-                Some(intersection) => intersection.sphere.grey_value,
-                _ => 0,
-            };
-            data.push(grey)
-        }
-    }
-    writer.write_image_data(&data).unwrap();
+    let apng = animation(&RenderOptions::default());
+    png_data_url(&apng.encode_with(&apng.optimise()))
 }
 
-struct Group {
-    spheres: Vec<Sphere>,
+/// The animation rendered at `frames_per_second`, with each frame's delay matched to the frame rate.
+#[wasm_bindgen]
+pub fn data_url_at_frame_rate(frames_per_second: f64) -> Result<String, String> {
+    let options = RenderOptions {
+        frames_per_second,
+        ..RenderOptions::default()
+    };
+    options.validate(&football_phases())?;
+    let apng = animation(&options);
+    Ok(png_data_url(&apng.encode_with(&apng.optimise())))
 }
 
-impl Group {
-    fn new(spheres: Vec<Sphere>) -> Self {
-        Self { spheres }
-    }
+/// The animation rendered with the `RenderOptions` given as JSON, for per-phase delays, a loop count
+/// or playing once.
+#[wasm_bindgen]
+pub fn data_url_with_options(options_json: &str) -> Result<String, String> {
+    let options = RenderOptions::from_json(options_json, &football_phases())?;
+    let apng = animation(&options);
+    Ok(png_data_url(&apng.encode_with(&apng.optimise())))
+}
 
-    fn dodecahedron(animation_fraction: f64, colour_scheme: ColourScheme) -> Self {
-        let grey_values = [30, 50, 70, 90];
-        Self::new(
-            [
-                (0.0, 1.0, PHI, grey_values[1]),
-                (0.0, -1.0, PHI, grey_values[3]),
-                (0.0, 1.0, -PHI, grey_values[2]),
-                (0.0, -1.0, -PHI, grey_values[3]),
-                (PHI, 0.0, 1.0, grey_values[2]),
-                (-PHI, 0.0, 1.0, grey_values[2]),
-                (PHI, 0.0, -1.0, grey_values[1]),
-                (-PHI, 0.0, -1.0, grey_values[0]),
-                (1.0, PHI, 0.0, grey_values[0]),
-                (-1.0, PHI, 0.0, grey_values[3]),
-                (1.0, -PHI, 0.0, grey_values[0]),
-                (-1.0, -PHI, 0.0, grey_values[1]),
-            ]
-            .iter()
-            .map(|values| Sphere {
-                centre: Point3d {
-                    x: values.0,
-                    y: values.1,
-                    z: values.2,
-                }
-                .normalise()
-                .expect("The point is not the origin.")
-                .rotate(animation_fraction * TAU, &Point3d::y_axis())
-                .expect("The y_axis vector is not zero.")
-                    * (4.0 * football_orbital_radius(animation_fraction * TAU) + 0.002)
-                    * 0.974
-                    + Point3d {
-                        x: football_horizontal_offset(animation_fraction * TAU) * 2.0,
-                        y: 0.0,
-                        z: 0.0,
-                    },
-                radius: 1.0,
-                grey_value: match colour_scheme {
-                    ColourScheme::Standard => 0,
-                    ColourScheme::Explanatory => values.3,
-                },
-            })
-            .collect(),
-        )
-    }
+/// Compare the size of the animation written as 8-bit grayscale with its size after optimising the
+/// encoding, as JSON.
+#[wasm_bindgen]
+pub fn encoding_report() -> String {
+    serde_json::to_string_pretty(&animation(&RenderOptions::default()).report())
+        .expect("The report contains no maps.")
+}
 
-    fn icosahedron(animation_fraction: f64, colour_scheme: ColourScheme) -> Self {
-        let grey_values = [255, 235, 215];
-        Self::new(
-            [
-                (PHI_SQUARED, 1.0, 0.0, grey_values[0]),
-                (-PHI_SQUARED, 1.0, 0.0, grey_values[1]),
-                (PHI_SQUARED, -1.0, 0.0, grey_values[2]),
-                (-PHI_SQUARED, -1.0, 0.0, grey_values[2]),
-                (1.0, 0.0, PHI_SQUARED, grey_values[0]),
-                (-1.0, 0.0, PHI_SQUARED, grey_values[2]),
-                (1.0, 0.0, -PHI_SQUARED, grey_values[0]),
-                (-1.0, 0.0, -PHI_SQUARED, grey_values[1]),
-                (0.0, -PHI_SQUARED, 1.0, grey_values[0]),
-                (0.0, PHI_SQUARED, 1.0, grey_values[1]),
-                (0.0, -PHI_SQUARED, -1.0, grey_values[2]),
-                (0.0, PHI_SQUARED, -1.0, grey_values[0]),
-                (PHI, PHI, PHI, grey_values[2]),
-                (-PHI, PHI, PHI, grey_values[0]),
-                (PHI, -PHI, PHI, grey_values[1]),
-                (-PHI, -PHI, PHI, grey_values[1]),
-                (PHI, PHI, -PHI, grey_values[1]),
-                (-PHI, PHI, -PHI, grey_values[2]),
-                (PHI, -PHI, -PHI, grey_values[1]),
-                (-PHI, -PHI, -PHI, grey_values[0]),
-            ]
-            .iter()
-            .map(|values| Sphere {
-                centre: Point3d {
-                    x: values.0,
-                    y: values.1,
-                    z: values.2,
-                }
-                .normalise()
-                .expect("The point is not the origin.")
-                .rotate(animation_fraction * TAU, &Point3d::y_axis())
-                .expect("The y_axis vector is not zero.")
-                    * (4.0 * football_orbital_radius(animation_fraction * TAU) + 0.002)
-                    + Point3d {
-                        x: -football_horizontal_offset(animation_fraction * TAU) * 2.0,
-                        y: 0.0,
-                        z: 0.0,
-                    },
-                radius: 1.0,
-                grey_value: match colour_scheme {
-                    ColourScheme::Standard => 255,
-                    ColourScheme::Explanatory => values.3,
-                },
-            })
-            .collect(),
-        )
-    }
+fn default_clock() -> Clock {
+    Clock::new(DURATION_SECONDS, DEFAULT_FRAMES_PER_SECOND)
+        .expect("The duration and default frame rate are positive.")
+}
 
-    fn football(animation_fraction: f64, colour_scheme: ColourScheme) -> Self {
-        Self::dodecahedron(animation_fraction, colour_scheme.clone())
-            + Self::icosahedron(animation_fraction, colour_scheme)
-    }
+fn animation(options: &RenderOptions<Parameters>) -> Apng {
+    let (scheme, finished_scheme) = options.colour_schemes();
+    render_with_progress(
+        options,
+        DURATION_SECONDS,
+        &football_phases(),
+        |animation_fraction| coloured_frame(animation_fraction, &options.parameters, &scheme),
+        &coloured_frame(0.0, &options.parameters, &finished_scheme),
+        |frame_index, frame_count| eprintln!("Football frame {frame_index:03} of {frame_count}"),
+    )
+}
 
-    fn rotate(&self, angle: f64, axis: &Point3d) -> Option<Self> {
-        match axis {
-            x if *x == Point3d::origin() => None,
-            _ => Some(Self {
-                spheres: self
-                    .spheres
-                    .iter()
-                    .filter_map(|sphere| sphere.rotate(angle, axis))
-                    .collect(),
-            }),
-        }
-    }
+/// Tile every `frame_step`th frame of the animation into one sprite sheet, rendering each frame at
+/// `tile_size` pixels square.
+#[wasm_bindgen]
+pub fn sprite_sheet(frame_step: u32, tile_size: u32) -> Result<SpriteSheet, String> {
+    let frame_step = frame_step.max(1);
+    let clock = default_clock();
+    let frames = (0..clock.frame_count())
+        .step_by(frame_step as usize)
+        .map(|frame_index| {
+            let animation_fraction = clock.animation_fraction(frame_index);
+            frame_pixels(tile_size, tile_size, &frame(animation_fraction).spheres)
+        });
+    SpriteSheet::new(
+        NAME,
+        frames,
+        tile_size,
+        tile_size,
+        (1000.0 * frame_step as f64 / DEFAULT_FRAMES_PER_SECOND).round() as u32,
+    )
 }
 
-impl Add for Group {
-    type Output = Self;
+/// Export the animation as a binary glTF (`.glb`) file, with one node per sphere and translation and
+/// scale keyframes for every frame.
+#[wasm_bindgen]
+pub fn glb() -> Result<Vec<u8>, String> {
+    let clock = default_clock();
+    let frames: Vec<Group> = (0..clock.frame_count())
+        .map(|frame_index| frame(clock.animation_fraction(frame_index)))
+        .collect();
+    gltf::glb(&frames, 1.0 / DEFAULT_FRAMES_PER_SECOND)
+}
 
-    fn add(self, rhs: Self) -> Self::Output {
-        let mut spheres = self.spheres;
-        spheres.extend(rhs.spheres);
-        Self { spheres }
-    }
+/// The description of the animation in the JSON scene format that `scene_animation_data_url` reads.
+#[wasm_bindgen]
+pub fn scene_description() -> String {
+    SCENE_DESCRIPTION.to_string()
 }
 
-impl Add<Point3d> for Group {
-    type Output = Self;
+/// Take apart the assembled group given as JSON, in the form `scene_json` gives, and put it back
+/// together as the `ExplodeOptions` given as JSON describe, rendered in the same way as the built-in
+/// animation at the default frame rate.
+#[wasm_bindgen]
+pub fn explode_data_url(group_json: &str, options_json: &str) -> Result<String, String> {
+    common::explode_data_url(group_json, options_json)
+}
 
-    fn add(self, rhs: Point3d) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() + rhs.clone())
-                .collect(),
-        }
-    }
+/// Check the movement of every sphere taken apart by `explode_data_url` for jumps in value or slope,
+/// and check that it loops back to the assembled group, as JSON.
+#[wasm_bindgen]
+pub fn explode_continuity_report(group_json: &str, options_json: &str) -> Result<String, String> {
+    common::explode_continuity_report(group_json, options_json)
 }
 
-impl Sub<Point3d> for Group {
-    type Output = Self;
+/// Morph between two groups given as JSON, in the form `scene_json` gives, such as the assembled
+/// beachball and the assembled starball from their own modules. The animation holds the first group,
+/// morphs into the second, holds it and morphs back, rendered in the same way as the built-in
+/// animation at the default frame rate. See `common::morph::Morph`.
+#[wasm_bindgen]
+pub fn morph_data_url(from_json: &str, to_json: &str) -> Result<String, String> {
+    common::morph_data_url(from_json, to_json)
+}
 
-    fn sub(self, rhs: Point3d) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() - rhs.clone())
-                .collect(),
-        }
-    }
+/// A novelty ball built at random from `seed`, coming apart and back together, rendered in the same
+/// way as the built-in animation at the default frame rate. See `common::random_ball::RandomBall`.
+#[wasm_bindgen]
+pub fn random_ball_data_url(seed: u32) -> String {
+    common::random_ball_data_url(seed)
 }
 
-impl Mul<f64> for Group {
-    type Output = Self;
+/// The random ball built from `seed` at `animation_fraction` as JSON, in the same form as
+/// `scene_json`, for keeping as a regression fixture.
+#[wasm_bindgen]
+pub fn random_ball_json(seed: u32, animation_fraction: f64) -> String {
+    common::random_ball_json(seed, animation_fraction)
+}
 
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() * rhs)
-                .collect(),
-        }
-    }
+/// Load an animation described in the JSON scene format and render it in the same way as the
+/// built-in animation, at the default frame rate.
+#[wasm_bindgen]
+pub fn scene_animation_data_url(json: &str) -> Result<String, String> {
+    common::scene_animation_data_url(json, Parameters::DEFAULT_SCHEME)
 }
 
-#[derive(Clone)]
-enum ColourScheme {
-    Standard,
-    Explanatory,
+/// The animation written as a Rhai script, in the form that `script_animation_data_url` reads.
+#[cfg(feature = "script")]
+#[wasm_bindgen]
+pub fn script() -> String {
+    SCRIPT.to_string()
 }
 
-#[derive(Clone)]
-struct Sphere {
-    centre: Point3d,
-    radius: f64,
-    grey_value: u8,
+/// Run an animation written as a Rhai script and render it in the same way as the built-in animation,
+/// at the default frame rate. See `common::script::Script` for what a script can use.
+#[cfg(feature = "script")]
+#[wasm_bindgen]
+pub fn script_animation_data_url(source: &str) -> Result<String, String> {
+    common::script_animation_data_url(source)
 }
 
-impl Sphere {
-    fn rotate(&self, angle: f64, axis: &Point3d) -> Option<Self> {
-        Some(Self {
-            centre: self.centre.rotate(angle, axis)?,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        })
+/// Render the named animation at a single `animation_fraction`, without rendering the rest of it, as
+/// the PNG file or raw grey values that `options_json` asks for.
+#[wasm_bindgen]
+pub fn render_frame(
+    name: &str,
+    animation_fraction: f64,
+    options_json: &str,
+) -> Result<Vec<u8>, String> {
+    if name != NAME {
+        return Err(format!("This module renders the {NAME}, not \"{name}\"."));
     }
+    if !animation_fraction.is_finite() {
+        return Err("The animation fraction is a finite number.".to_string());
+    }
+    let options = FrameOptions::<Parameters>::from_json(options_json)?;
+    let pixels = frame_pixels(
+        options.width,
+        options.height,
+        &coloured_frame(
+            animation_fraction,
+            &options.parameters,
+            &options.colour_scheme.scheme(&options.parameters),
+        )
+        .spheres,
+    );
+    Ok(match options.format {
+        FrameFormat::Png => grayscale_png(options.width, options.height, &pixels),
+        FrameFormat::Raw => pixels,
+    })
 }
 
-impl fmt::Display for Sphere {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "[{}, {}, {}],",
-            self.centre, self.radius, self.grey_value
-        )
-    }
+/// The scene at `animation_fraction` as JSON, which `scene_image_data_url` reads back exactly.
+#[wasm_bindgen]
+pub fn scene_json(animation_fraction: f64) -> String {
+    frame(animation_fraction).to_json()
 }
 
-impl Mul<f64> for Sphere {
-    type Output = Self;
+/// Render a scene exported by `scene_json` as a still PNG.
+#[wasm_bindgen]
+pub fn scene_image_data_url(json: &str) -> Result<String, String> {
+    common::scene_image_data_url(json)
+}
 
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self {
-            centre: self.centre * rhs,
-            radius: self.radius * rhs,
-            grey_value: self.grey_value,
-        }
-    }
+/// The named phases of the animation in order, with their start and end fractions and captions, as
+/// JSON.
+#[wasm_bindgen]
+pub fn phases_json() -> String {
+    serde_json::to_string(&football_phases()).expect("A phase contains no maps.")
 }
 
-impl Add<Point3d> for Sphere {
-    type Output = Self;
+/// The standard and explanatory colour schemes as JSON, to start a custom scheme from.
+#[wasm_bindgen]
+pub fn colour_schemes_json() -> String {
+    let scheme = |name| SchemeChoice::Named(name).scheme(&Parameters::default());
+    serde_json::json!({
+        "standard": scheme(SchemeName::Standard),
+        "explanatory": scheme(SchemeName::Explanatory),
+    })
+    .to_string()
+}
 
-    fn add(self, rhs: Point3d) -> Self::Output {
-        Self {
-            centre: self.centre + rhs,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        }
-    }
+/// The parameters that `RenderOptions` and `FrameOptions` accept to vary the ball, with their
+/// descriptions, types, defaults and ranges, as JSON.
+#[wasm_bindgen]
+pub fn parameter_schema_json() -> String {
+    parameters::schema_json::<Parameters>()
 }
 
-impl Sub<Point3d> for Sphere {
-    type Output = Self;
+/// A colour scheme as JSON that gives neighbouring spheres of the assembled football greys that differ as
+/// much as possible, to pass as a custom `colour_scheme`. See `common::contrast::contrast_scheme`.
+#[wasm_bindgen]
+pub fn contrast_scheme_json() -> String {
+    serde_json::to_string(&contrast_scheme(&coloured_frame(
+        0.0,
+        &Parameters::default(),
+        &Scheme::new(&[]),
+    )))
+    .expect("A scheme contains no maps.")
+}
 
-    fn sub(self, rhs: Point3d) -> Self::Output {
-        Self {
-            centre: self.centre - rhs,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        }
-    }
+/// The same as `contrast_scheme_json` for an animation described in the JSON scene format, which is
+/// assembled in its first frame.
+#[wasm_bindgen]
+pub fn scene_contrast_scheme_json(json: &str) -> Result<String, String> {
+    common::scene_contrast_scheme_json(json)
 }
 
-#[derive(Clone, PartialEq)]
-struct Point3d {
-    x: f64,
-    y: f64,
-    z: f64,
+/// Check every motion track for jumps in value or slope, and check that the end of the animation leads
+/// back into its first frame, as JSON.
+#[wasm_bindgen]
+pub fn continuity_report() -> String {
+    serde_json::to_string_pretty(&continuity()).expect("The report contains no maps.")
 }
 
-impl Point3d {
-    fn normalise(&self) -> Option<Self> {
-        let distance = Self::distance(self, &Self::origin());
-        if distance == 0.0 {
-            return None;
-        }
-        let x = self.x / distance;
-        let y = self.y / distance;
-        let z = self.z / distance;
-        Some(Self { x, y, z })
-    }
+fn continuity() -> ContinuityReport {
+    ContinuityReport::new(
+        FootballTrack::ALL
+            .iter()
+            .map(|track| (format!("{track:?}"), football_timeline(*track))),
+        frame,
+    )
+}
 
-    fn distance(a: &Self, b: &Self) -> f64 {
-        ((a.x - b.x).powf(2.0) + (a.y - b.y).powf(2.0) + (a.z - b.z).powf(2.0)).powf(0.5)
-    }
+/// Sample a path described in JSON at `samples` evenly spaced steps of progress from its start to its
+/// end inclusive, giving the points as JSON, for previewing the path a sphere would follow.
+#[wasm_bindgen]
+pub fn sample_path(json: &str, samples: u32) -> Result<String, String> {
+    common::sample_path(json, samples)
+}
 
-    fn origin() -> Self {
-        Self {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-        }
-    }
+/// Sample the named easing at `samples` evenly spaced points from 0.0 to 1.0 inclusive, for plotting
+/// its curve.
+#[wasm_bindgen]
+pub fn easing_curve(name: &str, samples: u32) -> Result<Vec<f64>, String> {
+    common::easing_curve(name, samples)
+}
 
-    // Based on https://en.wikipedia.org/wiki/Rotation_matrix#Rotation_matrix_from_axis_and_angle
-    fn rotate(&self, angle: f64, axis: &Self) -> Option<Self> {
-        let u = axis.normalise()?;
-        let s = angle.sin();
-        let c = angle.cos();
-        let d = 1.0 - c;
-        Some(Self {
-            x: self.x * (c + u.x.powf(2.0) * d)
-                + self.y * (u.x * u.y * d - u.z * s)
-                + self.z * (u.x * u.z * d + u.y * s),
-            y: self.x * (u.y * u.x * d + u.z * s)
-                + self.y * (c + u.y.powf(2.0) * d)
-                + self.z * (u.y * u.z * d - u.x * s),
-            z: self.x * (u.z * u.x * d - u.y * s)
-                + self.y * (u.z * u.y * d + u.x * s)
-                + self.z * (c + u.z.powf(2.0) * d),
+fn frame(animation_fraction: f64) -> Group {
+    let parameters = Parameters::default();
+    coloured_frame(
+        animation_fraction,
+        &parameters,
+        &SchemeChoice::default_for::<Parameters>().scheme(&parameters),
+    )
+}
+
+fn coloured_frame(animation_fraction: f64, parameters: &Parameters, scheme: &Scheme) -> Group {
+    scheme.colour(
+        Node::from(football(animation_fraction, parameters))
+            .rotate(TAU / 8.0, &Point3d::x_axis())
+            .expect("The x_axis vector is not zero.")
+            .scale(0.25)
+            .flatten(),
+    )
+}
+
+// The fourth value of each vertex is its shade in the explanatory scheme, chosen so that
+// neighbouring spheres differ
+fn dodecahedron(animation_fraction: f64, parameters: &Parameters) -> Group {
+    Group::new(
+        [
+            (0.0, 1.0, PHI, 1),
+            (0.0, -1.0, PHI, 3),
+            (0.0, 1.0, -PHI, 2),
+            (0.0, -1.0, -PHI, 3),
+            (PHI, 0.0, 1.0, 2),
+            (-PHI, 0.0, 1.0, 2),
+            (PHI, 0.0, -1.0, 1),
+            (-PHI, 0.0, -1.0, 0),
+            (1.0, PHI, 0.0, 0),
+            (-1.0, PHI, 0.0, 3),
+            (1.0, -PHI, 0.0, 0),
+            (-1.0, -PHI, 0.0, 1),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(index, values)| Sphere {
+            centre: Point3d {
+                x: values.0,
+                y: values.1,
+                z: values.2,
+            }
+            .normalise()
+            .expect("The point is not the origin.")
+            .rotate(animation_fraction * TAU, &Point3d::y_axis())
+            .expect("The y_axis vector is not zero.")
+                * (parameters.open_distance
+                    * football_timeline(FootballTrack::OrbitalRadius).sample(animation_fraction)
+                    + parameters.sphere_offset)
+                * parameters.dodecahedron_scale
+                + Point3d {
+                    x: football_timeline(FootballTrack::HorizontalOffset)
+                        .sample(animation_fraction)
+                        * parameters.separation,
+                    y: 0.0,
+                    z: 0.0,
+                },
+            radius: 1.0,
+            grey_value: UNCOLOURED,
+            name: Some(format!("dodecahedron {index}")),
+            tags: tag_set(&["dodecahedron", &format!("dodecahedron-shade-{}", values.3)]),
         })
-    }
+        .collect(),
+    )
+}
 
-    fn x_axis() -> Self {
-        Self {
-            x: 1.0,
-            y: 0.0,
-            z: 0.0,
-        }
-    }
+fn icosahedron(animation_fraction: f64, parameters: &Parameters) -> Group {
+    Group::new(
+        [
+            (PHI_SQUARED, 1.0, 0.0, 0),
+            (-PHI_SQUARED, 1.0, 0.0, 1),
+            (PHI_SQUARED, -1.0, 0.0, 2),
+            (-PHI_SQUARED, -1.0, 0.0, 2),
+            (1.0, 0.0, PHI_SQUARED, 0),
+            (-1.0, 0.0, PHI_SQUARED, 2),
+            (1.0, 0.0, -PHI_SQUARED, 0),
+            (-1.0, 0.0, -PHI_SQUARED, 1),
+            (0.0, -PHI_SQUARED, 1.0, 0),
+            (0.0, PHI_SQUARED, 1.0, 1),
+            (0.0, -PHI_SQUARED, -1.0, 2),
+            (0.0, PHI_SQUARED, -1.0, 0),
+            (PHI, PHI, PHI, 2),
+            (-PHI, PHI, PHI, 0),
+            (PHI, -PHI, PHI, 1),
+            (-PHI, -PHI, PHI, 1),
+            (PHI, PHI, -PHI, 1),
+            (-PHI, PHI, -PHI, 2),
+            (PHI, -PHI, -PHI, 1),
+            (-PHI, -PHI, -PHI, 0),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(index, values)| Sphere {
+            centre: Point3d {
+                x: values.0,
+                y: values.1,
+                z: values.2,
+            }
+            .normalise()
+            .expect("The point is not the origin.")
+            .rotate(animation_fraction * TAU, &Point3d::y_axis())
+            .expect("The y_axis vector is not zero.")
+                * (parameters.open_distance
+                    * football_timeline(FootballTrack::OrbitalRadius).sample(animation_fraction)
+                    + parameters.sphere_offset)
+                + Point3d {
+                    x: -football_timeline(FootballTrack::HorizontalOffset)
+                        .sample(animation_fraction)
+                        * parameters.separation,
+                    y: 0.0,
+                    z: 0.0,
+                },
+            radius: 1.0,
+            grey_value: UNCOLOURED,
+            name: Some(format!("icosahedron {index}")),
+            tags: tag_set(&["icosahedron", &format!("icosahedron-shade-{}", values.3)]),
+        })
+        .collect(),
+    )
+}
 
-    fn y_axis() -> Self {
-        Self {
-            x: 0.0,
-            y: 1.0,
-            z: 0.0,
-        }
-    }
+fn football(animation_fraction: f64, parameters: &Parameters) -> Group {
+    dodecahedron(animation_fraction, parameters) + icosahedron(animation_fraction, parameters)
 }
 
-impl Add for Point3d {
-    type Output = Self;
+#[derive(Clone, Copy, Debug)]
+enum FootballTrack {
+    OrbitalRadius,
+    HorizontalOffset,
+}
 
-    fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-            z: self.z + rhs.z,
-        }
+// One row per keyframe: the fraction of the way through the animation, followed by the value of each
+// track in the order they are listed in `FootballTrack`
+const FOOTBALL_KEYFRAMES: [(f64, [f64; 2]); 13] = [
+    (0.0, [0.0, 0.0]),
+    (1.0 / 12.0, [0.0, 0.0]),
+    (2.0 / 12.0, [1.0, 0.0]),
+    (3.0 / 12.0, [1.0, 0.0]),
+    (4.0 / 12.0, [1.0, 1.0]),
+    (5.0 / 12.0, [1.0, 1.0]),
+    (6.0 / 12.0, [0.0, 1.0]),
+    (7.0 / 12.0, [0.0, 1.0]),
+    (8.0 / 12.0, [1.0, 1.0]),
+    (9.0 / 12.0, [1.0, 1.0]),
+    (10.0 / 12.0, [1.0, 0.0]),
+    (11.0 / 12.0, [1.0, 0.0]),
+    (1.0, [0.0, 0.0]),
+];
+
+impl FootballTrack {
+    const ALL: [Self; 2] = [Self::OrbitalRadius, Self::HorizontalOffset];
+
+    fn easing(self) -> Box<dyn Easing> {
+        Box::new(PositiveCos)
     }
 }
 
-impl Sub for Point3d {
-    type Output = Self;
+fn football_timeline(track: FootballTrack) -> Timeline {
+    Timeline::from_table(&FOOTBALL_KEYFRAMES, track as usize).with_easing(track.easing())
+}
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        self + Self {
-            x: -rhs.x,
-            y: -rhs.y,
-            z: -rhs.z,
-        }
-    }
+// The name and caption of each phase, one for each step between rows of `FOOTBALL_KEYFRAMES`
+const FOOTBALL_PHASES: [(&str, &str); 12] = [
+    (
+        "Assembled",
+        "The football, made of 12 dark spheres and 20 light spheres.",
+    ),
+    ("Open", "Every sphere moves out from the centre."),
+    (
+        "Open pause",
+        "The 12 dark spheres and the 20 light spheres hang apart.",
+    ),
+    (
+        "Separate",
+        "The dark spheres and the light spheres slide apart sideways.",
+    ),
+    (
+        "Separate pause",
+        "The two sets of spheres hang side by side.",
+    ),
+    (
+        "Close each",
+        "Each set of spheres closes into a ball of its own.",
+    ),
+    (
+        "Two balls",
+        "The 12 dark spheres make one ball and the 20 light spheres make another.",
+    ),
+    ("Reopen", "Each ball opens out again."),
+    ("Reopen pause", "The two sets of spheres hang side by side."),
+    (
+        "Rejoin",
+        "The dark spheres and the light spheres slide back together.",
+    ),
+    ("Rejoin pause", "The two sets of spheres hang interleaved."),
+    (
+        "Close",
+        "Every sphere moves back in to make the football again.",
+    ),
+];
+
+fn football_phases() -> Vec<Phase> {
+    Phase::between_keyframes(&FOOTBALL_KEYFRAMES, &FOOTBALL_PHASES)
 }
 
-impl Mul<f64> for Point3d {
-    type Output = Self;
+// The dodecahedron makes the black patches of the football, and the icosahedron the white
+const STANDARD_SCHEME: &[(&str, u8)] = &[("dodecahedron", 0), ("icosahedron", 255)];
+
+// Each shade is a different grey, so that neighbouring spheres can be told apart
+const EXPLANATORY_SCHEME: &[(&str, u8)] = &[
+    ("dodecahedron-shade-0", 30),
+    ("dodecahedron-shade-1", 50),
+    ("dodecahedron-shade-2", 70),
+    ("dodecahedron-shade-3", 90),
+    ("icosahedron-shade-0", 255),
+    ("icosahedron-shade-1", 235),
+    ("icosahedron-shade-2", 215),
+];
+
+/// The parameters that vary the football, read from JSON and described in `PARAMETER_SCHEMA`.
+/// Every field is optional.
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Parameters {
+    dodecahedron_scale: f64,
+    sphere_offset: f64,
+    open_distance: f64,
+    separation: f64,
+}
 
-    fn mul(self, rhs: f64) -> Self::Output {
+impl Default for Parameters {
+    fn default() -> Self {
         Self {
-            x: self.x * rhs,
-            y: self.y * rhs,
-            z: self.z * rhs,
+            dodecahedron_scale: 0.974,
+            sphere_offset: 0.002,
+            open_distance: 4.0,
+            separation: 2.0,
         }
     }
 }
 
-impl fmt::Display for Point3d {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}, {}, {}", self.x, self.y, self.z)
+const PARAMETER_SCHEMA: &[ParameterSchema] = &[
+    ParameterSchema {
+        name: "dodecahedron_scale",
+        description: "How far out the dodecahedron spheres sit compared with the icosahedron spheres, which sets how large the black patches are.",
+        min: 0.9,
+        max: 1.0,
+    },
+    ParameterSchema {
+        name: "sphere_offset",
+        description: "How far every sphere sits out from the centre when the football is assembled, so that the spheres of the two solids cover each other by different amounts.",
+        min: 0.0,
+        max: 0.01,
+    },
+    ParameterSchema {
+        name: "open_distance",
+        description: "How far the spheres move out from the centre as the football opens, in sphere radii.",
+        min: 0.0,
+        max: 8.0,
+    },
+    ParameterSchema {
+        name: "separation",
+        description: "How far each set of spheres slides sideways as the two sets separate, in sphere radii.",
+        min: 0.0,
+        max: 4.0,
+    },
+];
+
+impl AnimationParameters for Parameters {
+    const SCHEMA: &'static [ParameterSchema] = PARAMETER_SCHEMA;
+    // The animation tells apart spheres that look alike unless another scheme is chosen
+    const DEFAULT_SCHEME: SchemeName = SchemeName::Explanatory;
+
+    fn standard_scheme(&self) -> Scheme {
+        Scheme::new(STANDARD_SCHEME)
     }
-}
 
-struct Point2d {
-    x: f64,
-    y: f64,
+    fn explanatory_scheme(&self) -> Scheme {
+        Scheme::new(EXPLANATORY_SCHEME)
+    }
 }
 
-impl Point2d {
-    fn distance(a: Self, b: Self) -> f64 {
-        ((a.x - b.x).powf(2.0) + (a.y - b.y).powf(2.0)).powf(0.5)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::animation_checks::AnimationChecks;
+    use common::easing::positive_cos;
+
+    #[test]
+    fn every_track_is_continuous() {
+        for track in continuity().tracks {
+            assert_eq!(
+                track.discontinuities,
+                vec![],
+                "{} is continuous.",
+                track.track
+            );
+        }
+    }
+
+    #[test]
+    fn end_of_the_animation_leads_into_the_first_frame() {
+        assert_eq!(continuity().unmatched_at_loop, Vec::<String>::new());
     }
-}
 
-struct Intersection {
-    sphere: Sphere,
-    distance: f64,
-}
-
-fn sphere_with_intersection_distance(
-    x: f64,
-    y: f64,
-) -> impl FnMut(&Sphere) -> Option<Intersection> {
-    move |sphere| {
-        let distance = Point2d::distance(
-            Point2d { x, y },
-            Point2d {
-                x: sphere.centre.x as f64,
-                y: sphere.centre.y as f64,
-            },
-        );
-        let radius = sphere.radius as f64;
-        if distance < radius {
-            Some(Intersection {
-                sphere: sphere.clone(),
-                distance: 1000.0 + sphere.centre.z as f64
-                    - (radius.powf(2.0) - distance.powf(2.0)).powf(0.5)
-                    - 1000.0,
-            })
-        } else {
-            None
+    // The piecewise functions the keyframes replaced, each giving a track's value at angle `a`, which
+    // runs from 0 to TAU over the animation
+    fn football_orbital_radius(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => football_orbital_radius(a + TAU),
+            x if x < TAU / 12.0 => 0.0,
+            x if x < TAU / 6.0 => positive_cos(6.0 * a),
+            x if x < TAU * 5.0 / 12.0 => 1.0,
+            x if x < TAU / 2.0 => 1.0 - positive_cos(6.0 * a),
+            x if x < TAU * 7.0 / 12.0 => 0.0,
+            x if x < TAU * 2.0 / 3.0 => positive_cos(6.0 * a),
+            x if x < TAU * 11.0 / 12.0 => 1.0,
+            x if x < TAU => 1.0 - positive_cos(6.0 * a),
+            _ => football_orbital_radius(a - TAU),
         }
     }
-}
 
-fn football_orbital_radius(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => football_orbital_radius(a + TAU),
-        x if x < TAU / 12.0 => 0.0,
-        x if x < TAU / 6.0 => positive_cos(6.0 * a),
-        x if x < TAU * 5.0 / 12.0 => 1.0,
-        x if x < TAU / 2.0 => 1.0 - positive_cos(6.0 * a),
-        x if x < TAU * 7.0 / 12.0 => 0.0,
-        x if x < TAU * 2.0 / 3.0 => positive_cos(6.0 * a),
-        x if x < TAU * 11.0 / 12.0 => 1.0,
-        x if x < TAU => 1.0 - positive_cos(6.0 * a),
-        _ => football_orbital_radius(a - TAU),
+    fn football_horizontal_offset(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => football_horizontal_offset(a + TAU),
+            x if x < TAU / 4.0 => 0.0,
+            x if x < TAU / 3.0 => positive_cos(6.0 * a),
+            x if x < TAU * 3.0 / 4.0 => 1.0,
+            x if x < TAU * 5.0 / 6.0 => 1.0 - positive_cos(6.0 * a),
+            x if x < TAU => 0.0,
+            _ => football_horizontal_offset(a - TAU),
+        }
     }
-}
 
-fn football_horizontal_offset(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => football_horizontal_offset(a + TAU),
-        x if x < TAU / 4.0 => 0.0,
-        x if x < TAU / 3.0 => positive_cos(6.0 * a),
-        x if x < TAU * 3.0 / 4.0 => 1.0,
-        x if x < TAU * 5.0 / 6.0 => 1.0 - positive_cos(6.0 * a),
-        x if x < TAU => 0.0,
-        _ => football_horizontal_offset(a - TAU),
+    #[test]
+    fn the_keyframes_match_the_original_piecewise_functions() {
+        for step in -22..=242 {
+            let a = step as f64 / 220.0;
+            for (track, original) in [
+                (
+                    FootballTrack::OrbitalRadius,
+                    football_orbital_radius as fn(f64) -> f64,
+                ),
+                (FootballTrack::HorizontalOffset, football_horizontal_offset),
+            ] {
+                let sampled = football_timeline(track).sample(a);
+                assert!(
+                    (sampled - original(a * TAU)).abs() < 1e-9,
+                    "{track:?} at {a} is {sampled} rather than {}.",
+                    original(a * TAU)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn how_far_the_football_opens_only_changes_it_while_open() {
+        let frame = |animation_fraction, parameters| {
+            coloured_frame(animation_fraction, &parameters, &Scheme::new(&[])).to_json()
+        };
+        let wider = || Parameters {
+            open_distance: 6.0,
+            separation: 3.0,
+            ..Parameters::default()
+        };
+        assert_eq!(frame(0.0, wider()), frame(0.0, Parameters::default()));
+        assert_ne!(frame(0.5, wider()), frame(0.5, Parameters::default()));
     }
-}
 
-fn positive_cos(a: f64) -> f64 {
-    (a.cos() + 1.0) * 0.5
+    #[test]
+    fn the_checks_shared_by_every_animation_pass() {
+        AnimationChecks {
+            name: NAME,
+            duration_seconds: DURATION_SECONDS,
+            scene_description: SCENE_DESCRIPTION,
+            #[cfg(feature = "script")]
+            script: SCRIPT,
+            coloured_frame,
+            render_frame,
+        }
+        .run();
+    }
 }
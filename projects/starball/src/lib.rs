@@ -1,499 +1,791 @@
-use base64::prelude::*;
+use common::apng::Apng;
+use common::clock::Clock;
+use common::contrast::contrast_scheme;
+use common::easing::{Easing, PositiveCos};
+use common::frame_options::{FrameFormat, FrameOptions};
+use common::gltf;
+use common::palette::{Scheme, SchemeChoice, SchemeName, UNCOLOURED};
+use common::parameters::{self, AnimationParameters, ParameterSchema};
+use common::phase::Phase;
+use common::render_options::RenderOptions;
+use common::scene_graph::Node;
+use common::sprite_sheet::SpriteSheet;
+use common::timeline::Timeline;
+use common::validation::ContinuityReport;
+use common::{
+    frame_pixels, grayscale_png, png_data_url, render, tag_set, Group, Point3d, Sphere,
+    DEFAULT_FRAMES_PER_SECOND,
+};
+use serde::{Deserialize, Serialize};
 use std::f64::consts::TAU;
-use std::fmt;
-use std::io::BufWriter;
-use std::io::Cursor;
-use std::ops::{Add, Mul, Sub};
 use wasm_bindgen::prelude::*;
-const WIDTH: u32 = 1024;
-const HEIGHT: u32 = 1024;
+const NAME: &str = "starball";
+const SCENE_DESCRIPTION: &str = include_str!("../scenes/starball.json");
+#[cfg(feature = "script")]
+const SCRIPT: &str = include_str!("../scripts/starball.rhai");
+const DURATION_SECONDS: f64 = 12.0;
+// The star spheres are a little smaller than the ball so that only the part above it shows
+const STAR_SPHERE_RADIUS: f64 = 0.96;
 
 #[wasm_bindgen]
 pub fn data_url() -> String {
-    let total_frames = 600;
-    let mut fake_file: Cursor<Vec<u8>> = Cursor::new(Vec::new());
-    {
-        // Start a block so that the APNG file will be closed after all of the frames have been written
-        let w = &mut BufWriter::new(&mut fake_file);
-
-        let mut encoder = png::Encoder::new(w, WIDTH, HEIGHT);
-        encoder.set_color(png::ColorType::Grayscale);
-        encoder.set_depth(png::BitDepth::Eight);
-        match encoder.set_animated(total_frames, 0) {
-            Ok(_) => (),
-            Err(error) => eprintln!("encoder.set_animated result: {error}"),
-        };
-        match encoder.set_frame_delay(2, 100) {
-            Ok(_) => (),
-            Err(error) => eprintln!("encoder.set_frame_delay result: {error}"),
-        };
-        let mut writer = encoder.write_header().unwrap();
-
-        for frame_index in 0..total_frames {
-            let animation_fraction = frame_index as f64 / total_frames as f64;
-            let starball = Group::starball(animation_fraction, ColourScheme::Explanatory)
-                .rotate(TAU / 8.0, &Point3d::x_axis())
-                .expect("The x_axis vector is not zero.")
-                * 0.25;
-            save_apng_frame(&mut writer, WIDTH, HEIGHT, &starball.spheres);
-        }
-    }
+    let apng = animation(&RenderOptions::default());
+    png_data_url(&apng.encode_with(&apng.optimise()))
+}
 
-    let base64_data = BASE64_STANDARD.encode(fake_file.get_ref());
-    format!("data:image/png;base64,{}", base64_data)
-}
-
-fn save_apng_frame(
-    writer: &mut png::Writer<&mut BufWriter<&mut Cursor<Vec<u8>>>>,
-    width: u32,
-    height: u32,
-    spheres: &[Sphere],
-) {
-    let mut data = vec![];
-    for y in 0..width {
-        for x in 0..height {
-            let normalised_x: f64 = x as f64 / width as f64 * 2.0 - 1.0;
-            let normalised_y: f64 = y as f64 / height as f64 * 2.0 - 1.0;
-            let result = spheres
-                .iter()
-                .filter_map(sphere_with_intersection_distance(
-                    normalised_x,
-                    normalised_y,
-                ))
-                .reduce(|current, next| {
-                    if next.distance < current.distance {
-                        next
-                    } else {
-                        current
-                    }
-                });
-            let grey = match result {
-                Some(intersection) => intersection.sphere.grey_value,
-                _ => 0,
-            };
-            data.push(grey)
-        }
-    }
-    writer.write_image_data(&data).unwrap();
+/// The animation rendered at `frames_per_second`, with each frame's delay matched to the frame rate.
+#[wasm_bindgen]
+pub fn data_url_at_frame_rate(frames_per_second: f64) -> Result<String, String> {
+    let options = RenderOptions {
+        frames_per_second,
+        ..RenderOptions::default()
+    };
+    options.validate(&starball_phases())?;
+    let apng = animation(&options);
+    Ok(png_data_url(&apng.encode_with(&apng.optimise())))
 }
 
-struct Group {
-    spheres: Vec<Sphere>,
+/// The animation rendered with the `RenderOptions` given as JSON, for per-phase delays, a loop count
+/// or playing once.
+#[wasm_bindgen]
+pub fn data_url_with_options(options_json: &str) -> Result<String, String> {
+    let options = RenderOptions::from_json(options_json, &starball_phases())?;
+    let apng = animation(&options);
+    Ok(png_data_url(&apng.encode_with(&apng.optimise())))
 }
 
-impl Group {
-    fn new(spheres: Vec<Sphere>) -> Self {
-        Self { spheres }
-    }
+/// Compare the size of the animation written as 8-bit grayscale with its size after optimising the
+/// encoding, as JSON.
+#[wasm_bindgen]
+pub fn encoding_report() -> String {
+    serde_json::to_string_pretty(&animation(&RenderOptions::default()).report())
+        .expect("The report contains no maps.")
+}
 
-    fn starball(animation_fraction: f64, colour_scheme: ColourScheme) -> Self {
-        let upper_sphere = Sphere {
-            centre: Point3d {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-            },
-            radius: 1.0,
-            grey_value: match colour_scheme {
-                ColourScheme::Standard => 210,
-                ColourScheme::Explanatory => 255,
-            },
-        };
-        let lower_sphere = Sphere {
-            centre: Point3d {
-                x: 0.0,
-                y: 0.0008 + starball_lower_sphere_offset(animation_fraction) * 4.8,
-                z: 0.0,
-            },
-            radius: 1.0,
-            grey_value: match colour_scheme {
-                ColourScheme::Standard => 210,
-                ColourScheme::Explanatory => 255,
-            },
-        };
-        let stripe_sphere = Sphere {
-            centre: Point3d {
-                x: 0.0,
-                y: 0.0004 + starball_stripe_sphere_offset(animation_fraction) * 2.4,
-                z: 0.0,
-            },
-            radius: 1.00012,
-            grey_value: match colour_scheme {
-                ColourScheme::Standard => 50,
-                ColourScheme::Explanatory => 50,
-            },
-        };
-        let star_spheres = (0..10).map(|index| Sphere {
-            centre: Point3d {
-                x: 0.0,
-                y: -0.044 - starball_star_sphere_offset(animation_fraction) * 3.4,
-                z: 0.0,
-            }
-            .rotate(
-                TAU / 21.0
-                    + TAU / 35.0 * (index % 2) as f64
-                    + starball_star_sphere_z_rotation(animation_fraction) * TAU / 7.0,
-                &Point3d::z_axis(),
-            )
-            .expect("The z_axis vector is not zero.")
-            .rotate(
-                TAU * animation_fraction + TAU / 10.0 * index as f64,
-                &Point3d::y_axis(),
-            )
-            .expect("The y_axis vector is not zero."),
-            radius: 0.96,
-            grey_value: match colour_scheme {
-                ColourScheme::Standard => 100 + 110 * (index % 2),
-                ColourScheme::Explanatory => 100 + 110 * (index % 2) + 5 * index,
-            },
+fn default_clock() -> Clock {
+    Clock::new(DURATION_SECONDS, DEFAULT_FRAMES_PER_SECOND)
+        .expect("The duration and default frame rate are positive.")
+}
+
+fn animation(options: &RenderOptions<Parameters>) -> Apng {
+    let (scheme, finished_scheme) = options.colour_schemes();
+    render(
+        options,
+        DURATION_SECONDS,
+        &starball_phases(),
+        |animation_fraction| coloured_frame(animation_fraction, &options.parameters, &scheme),
+        &coloured_frame(0.0, &options.parameters, &finished_scheme),
+    )
+}
+
+/// Tile every `frame_step`th frame of the animation into one sprite sheet, rendering each frame at
+/// `tile_size` pixels square.
+#[wasm_bindgen]
+pub fn sprite_sheet(frame_step: u32, tile_size: u32) -> Result<SpriteSheet, String> {
+    let frame_step = frame_step.max(1);
+    let clock = default_clock();
+    let frames = (0..clock.frame_count())
+        .step_by(frame_step as usize)
+        .map(|frame_index| {
+            let animation_fraction = clock.animation_fraction(frame_index);
+            frame_pixels(tile_size, tile_size, &frame(animation_fraction).spheres)
         });
+    SpriteSheet::new(
+        NAME,
+        frames,
+        tile_size,
+        tile_size,
+        (1000.0 * frame_step as f64 / DEFAULT_FRAMES_PER_SECOND).round() as u32,
+    )
+}
 
-        let mut spheres = vec![upper_sphere, lower_sphere, stripe_sphere];
-        spheres.extend(star_spheres);
+/// Export the animation as a binary glTF (`.glb`) file, with one node per sphere and translation and
+/// scale keyframes for every frame.
+#[wasm_bindgen]
+pub fn glb() -> Result<Vec<u8>, String> {
+    let clock = default_clock();
+    let frames: Vec<Group> = (0..clock.frame_count())
+        .map(|frame_index| frame(clock.animation_fraction(frame_index)))
+        .collect();
+    gltf::glb(&frames, 1.0 / DEFAULT_FRAMES_PER_SECOND)
+}
 
-        Self::new(spheres)
-    }
+/// The description of the animation in the JSON scene format that `scene_animation_data_url` reads.
+#[wasm_bindgen]
+pub fn scene_description() -> String {
+    SCENE_DESCRIPTION.to_string()
+}
 
-    fn rotate(&self, angle: f64, axis: &Point3d) -> Option<Self> {
-        match axis {
-            x if *x == Point3d::origin() => None,
-            _ => Some(Self {
-                spheres: self
-                    .spheres
-                    .iter()
-                    .filter_map(|sphere| sphere.rotate(angle, axis))
-                    .collect(),
-            }),
-        }
-    }
+/// Take apart the assembled group given as JSON, in the form `scene_json` gives, and put it back
+/// together as the `ExplodeOptions` given as JSON describe, rendered in the same way as the built-in
+/// animation at the default frame rate.
+#[wasm_bindgen]
+pub fn explode_data_url(group_json: &str, options_json: &str) -> Result<String, String> {
+    common::explode_data_url(group_json, options_json)
 }
 
-impl Add for Group {
-    type Output = Self;
+/// Check the movement of every sphere taken apart by `explode_data_url` for jumps in value or slope,
+/// and check that it loops back to the assembled group, as JSON.
+#[wasm_bindgen]
+pub fn explode_continuity_report(group_json: &str, options_json: &str) -> Result<String, String> {
+    common::explode_continuity_report(group_json, options_json)
+}
 
-    fn add(self, rhs: Self) -> Self::Output {
-        let mut spheres = self.spheres;
-        spheres.extend(rhs.spheres);
-        Self { spheres }
-    }
+/// Morph between two groups given as JSON, in the form `scene_json` gives, such as the assembled
+/// beachball and the assembled starball from their own modules. The animation holds the first group,
+/// morphs into the second, holds it and morphs back, rendered in the same way as the built-in
+/// animation at the default frame rate. See `common::morph::Morph`.
+#[wasm_bindgen]
+pub fn morph_data_url(from_json: &str, to_json: &str) -> Result<String, String> {
+    common::morph_data_url(from_json, to_json)
 }
 
-impl Add<Point3d> for Group {
-    type Output = Self;
+/// A novelty ball built at random from `seed`, coming apart and back together, rendered in the same
+/// way as the built-in animation at the default frame rate. See `common::random_ball::RandomBall`.
+#[wasm_bindgen]
+pub fn random_ball_data_url(seed: u32) -> String {
+    common::random_ball_data_url(seed)
+}
 
-    fn add(self, rhs: Point3d) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() + rhs.clone())
-                .collect(),
-        }
-    }
+/// The random ball built from `seed` at `animation_fraction` as JSON, in the same form as
+/// `scene_json`, for keeping as a regression fixture.
+#[wasm_bindgen]
+pub fn random_ball_json(seed: u32, animation_fraction: f64) -> String {
+    common::random_ball_json(seed, animation_fraction)
 }
 
-impl Sub<Point3d> for Group {
-    type Output = Self;
+/// Load an animation described in the JSON scene format and render it in the same way as the
+/// built-in animation, at the default frame rate.
+#[wasm_bindgen]
+pub fn scene_animation_data_url(json: &str) -> Result<String, String> {
+    common::scene_animation_data_url(json, Parameters::DEFAULT_SCHEME)
+}
 
-    fn sub(self, rhs: Point3d) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() - rhs.clone())
-                .collect(),
-        }
-    }
+/// The animation written as a Rhai script, in the form that `script_animation_data_url` reads.
+#[cfg(feature = "script")]
+#[wasm_bindgen]
+pub fn script() -> String {
+    SCRIPT.to_string()
 }
 
-impl Mul<f64> for Group {
-    type Output = Self;
+/// Run an animation written as a Rhai script and render it in the same way as the built-in animation,
+/// at the default frame rate. See `common::script::Script` for what a script can use.
+#[cfg(feature = "script")]
+#[wasm_bindgen]
+pub fn script_animation_data_url(source: &str) -> Result<String, String> {
+    common::script_animation_data_url(source)
+}
 
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self {
-            spheres: self
-                .spheres
-                .iter()
-                .map(|sphere| sphere.clone() * rhs)
-                .collect(),
-        }
+/// Render the named animation at a single `animation_fraction`, without rendering the rest of it, as
+/// the PNG file or raw grey values that `options_json` asks for.
+#[wasm_bindgen]
+pub fn render_frame(
+    name: &str,
+    animation_fraction: f64,
+    options_json: &str,
+) -> Result<Vec<u8>, String> {
+    if name != NAME {
+        return Err(format!("This module renders the {NAME}, not \"{name}\"."));
+    }
+    if !animation_fraction.is_finite() {
+        return Err("The animation fraction is a finite number.".to_string());
     }
+    let options = FrameOptions::<Parameters>::from_json(options_json)?;
+    let pixels = frame_pixels(
+        options.width,
+        options.height,
+        &coloured_frame(
+            animation_fraction,
+            &options.parameters,
+            &options.colour_scheme.scheme(&options.parameters),
+        )
+        .spheres,
+    );
+    Ok(match options.format {
+        FrameFormat::Png => grayscale_png(options.width, options.height, &pixels),
+        FrameFormat::Raw => pixels,
+    })
 }
 
-#[derive(Clone)]
-enum ColourScheme {
-    Standard,
-    Explanatory,
+/// The scene at `animation_fraction` as JSON, which `scene_image_data_url` reads back exactly.
+#[wasm_bindgen]
+pub fn scene_json(animation_fraction: f64) -> String {
+    frame(animation_fraction).to_json()
 }
 
-#[derive(Clone)]
-struct Sphere {
-    centre: Point3d,
-    radius: f64,
-    grey_value: u8,
+/// Render a scene exported by `scene_json` as a still PNG.
+#[wasm_bindgen]
+pub fn scene_image_data_url(json: &str) -> Result<String, String> {
+    common::scene_image_data_url(json)
 }
 
-impl Sphere {
-    fn rotate(&self, angle: f64, axis: &Point3d) -> Option<Self> {
-        Some(Self {
-            centre: self.centre.rotate(angle, axis)?,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        })
-    }
+/// The named phases of the animation in order, with their start and end fractions and captions, as
+/// JSON.
+#[wasm_bindgen]
+pub fn phases_json() -> String {
+    serde_json::to_string(&starball_phases()).expect("A phase contains no maps.")
 }
 
-impl fmt::Display for Sphere {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "[{}, {}, {}],",
-            self.centre, self.radius, self.grey_value
-        )
-    }
+/// The standard and explanatory colour schemes as JSON, to start a custom scheme from.
+#[wasm_bindgen]
+pub fn colour_schemes_json() -> String {
+    let scheme = |name| SchemeChoice::Named(name).scheme(&Parameters::default());
+    serde_json::json!({
+        "standard": scheme(SchemeName::Standard),
+        "explanatory": scheme(SchemeName::Explanatory),
+    })
+    .to_string()
 }
 
-impl Mul<f64> for Sphere {
-    type Output = Self;
+/// The parameters that `RenderOptions` and `FrameOptions` accept to vary the ball, with their
+/// descriptions, types, defaults and ranges, as JSON.
+#[wasm_bindgen]
+pub fn parameter_schema_json() -> String {
+    parameters::schema_json::<Parameters>()
+}
 
-    fn mul(self, rhs: f64) -> Self::Output {
-        Self {
-            centre: self.centre * rhs,
-            radius: self.radius * rhs,
-            grey_value: self.grey_value,
-        }
-    }
+/// A colour scheme as JSON that gives neighbouring spheres of the assembled starball greys that differ as
+/// much as possible, to pass as a custom `colour_scheme`. See `common::contrast::contrast_scheme`.
+#[wasm_bindgen]
+pub fn contrast_scheme_json() -> String {
+    serde_json::to_string(&contrast_scheme(&coloured_frame(
+        0.0,
+        &Parameters::default(),
+        &Scheme::new(&[]),
+    )))
+    .expect("A scheme contains no maps.")
 }
 
-impl Add<Point3d> for Sphere {
-    type Output = Self;
+/// The same as `contrast_scheme_json` for an animation described in the JSON scene format, which is
+/// assembled in its first frame.
+#[wasm_bindgen]
+pub fn scene_contrast_scheme_json(json: &str) -> Result<String, String> {
+    common::scene_contrast_scheme_json(json)
+}
 
-    fn add(self, rhs: Point3d) -> Self::Output {
-        Self {
-            centre: self.centre + rhs,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        }
-    }
+/// Check every motion track for jumps in value or slope, and check that the end of the animation leads
+/// back into its first frame, as JSON.
+#[wasm_bindgen]
+pub fn continuity_report() -> String {
+    serde_json::to_string_pretty(&continuity()).expect("The report contains no maps.")
 }
 
-impl Sub<Point3d> for Sphere {
-    type Output = Self;
+fn continuity() -> ContinuityReport {
+    ContinuityReport::new(
+        StarballTrack::ALL
+            .iter()
+            .map(|track| (format!("{track:?}"), starball_timeline(*track))),
+        frame,
+    )
+}
 
-    fn sub(self, rhs: Point3d) -> Self::Output {
-        Self {
-            centre: self.centre - rhs,
-            radius: self.radius,
-            grey_value: self.grey_value,
-        }
-    }
+/// Sample a path described in JSON at `samples` evenly spaced steps of progress from its start to its
+/// end inclusive, giving the points as JSON, for previewing the path a sphere would follow.
+#[wasm_bindgen]
+pub fn sample_path(json: &str, samples: u32) -> Result<String, String> {
+    common::sample_path(json, samples)
 }
 
-#[derive(Clone, PartialEq)]
-struct Point3d {
-    x: f64,
-    y: f64,
-    z: f64,
+/// Sample the named easing at `samples` evenly spaced points from 0.0 to 1.0 inclusive, for plotting
+/// its curve.
+#[wasm_bindgen]
+pub fn easing_curve(name: &str, samples: u32) -> Result<Vec<f64>, String> {
+    common::easing_curve(name, samples)
 }
 
-impl Point3d {
-    fn normalise(&self) -> Option<Self> {
-        let distance = Self::distance(self, &Self::origin());
-        if distance == 0.0 {
-            return None;
-        }
-        let x = self.x / distance;
-        let y = self.y / distance;
-        let z = self.z / distance;
-        Some(Self { x, y, z })
-    }
+fn frame(animation_fraction: f64) -> Group {
+    let parameters = Parameters::default();
+    coloured_frame(
+        animation_fraction,
+        &parameters,
+        &SchemeChoice::default_for::<Parameters>().scheme(&parameters),
+    )
+}
 
-    fn distance(a: &Self, b: &Self) -> f64 {
-        ((a.x - b.x).powf(2.0) + (a.y - b.y).powf(2.0) + (a.z - b.z).powf(2.0)).powf(0.5)
-    }
+fn coloured_frame(animation_fraction: f64, parameters: &Parameters, scheme: &Scheme) -> Group {
+    scheme.colour(
+        Node::from(starball(animation_fraction, parameters))
+            .rotate(TAU / 8.0, &Point3d::x_axis())
+            .expect("The x_axis vector is not zero.")
+            .scale(0.25)
+            .flatten(),
+    )
+}
 
-    fn origin() -> Self {
-        Self {
+fn starball(animation_fraction: f64, parameters: &Parameters) -> Group {
+    let upper_sphere = Sphere {
+        centre: Point3d {
             x: 0.0,
             y: 0.0,
             z: 0.0,
-        }
-    }
-
-    // Based on https://en.wikipedia.org/wiki/Rotation_matrix#Rotation_matrix_from_axis_and_angle
-    fn rotate(&self, angle: f64, axis: &Self) -> Option<Self> {
-        let u = axis.normalise()?;
-        let s = angle.sin();
-        let c = angle.cos();
-        let d = 1.0 - c;
-        Some(Self {
-            x: self.x * (c + u.x.powf(2.0) * d)
-                + self.y * (u.x * u.y * d - u.z * s)
-                + self.z * (u.x * u.z * d + u.y * s),
-            y: self.x * (u.y * u.x * d + u.z * s)
-                + self.y * (c + u.y.powf(2.0) * d)
-                + self.z * (u.y * u.z * d - u.x * s),
-            z: self.x * (u.z * u.x * d - u.y * s)
-                + self.y * (u.z * u.y * d + u.x * s)
-                + self.z * (c + u.z.powf(2.0) * d),
-        })
-    }
-
-    fn x_axis() -> Self {
-        Self {
-            x: 1.0,
-            y: 0.0,
+        },
+        radius: 1.0,
+        grey_value: UNCOLOURED,
+        name: Some("upper sphere".to_string()),
+        tags: tag_set(&["ball"]),
+    };
+    let lower_sphere = Sphere {
+        centre: Point3d {
+            x: 0.0,
+            y: parameters.lower_sphere_offset
+                + starball_timeline(StarballTrack::LowerSphereOffset).sample(animation_fraction)
+                    * parameters.lower_sphere_travel,
             z: 0.0,
-        }
-    }
-
-    fn y_axis() -> Self {
-        Self {
+        },
+        radius: 1.0,
+        grey_value: UNCOLOURED,
+        name: Some("lower sphere".to_string()),
+        tags: tag_set(&["ball"]),
+    };
+    let stripe_sphere = Sphere {
+        centre: Point3d {
             x: 0.0,
-            y: 1.0,
+            y: parameters.stripe_sphere_offset
+                + starball_timeline(StarballTrack::StripeSphereOffset).sample(animation_fraction)
+                    * parameters.stripe_sphere_travel,
             z: 0.0,
-        }
-    }
-
-    fn z_axis() -> Self {
-        Self {
+        },
+        radius: 1.0 + parameters.stripe_width,
+        grey_value: UNCOLOURED,
+        name: Some("stripe sphere".to_string()),
+        tags: tag_set(&["stripe"]),
+    };
+    // Each star sphere sits a little above the middle of the ball and shows as a cap on its surface.
+    // The ring of point spheres is tilted away from the top of the ball by a third of the star's
+    // points times its depth, so that a star with more points has a wider ring, but by no more than
+    // three quarters of the angle across a cap, so that the caps still cover the middle of the star.
+    // The star spheres turn three times as far again to show how the points alternate, but never past
+    // the side of the ball. The default 5 points with a depth of TAU / 35 give a tilt of TAU / 21 and
+    // a turn of TAU / 7.
+    let cap_angle = ((1.0 + parameters.star_sphere_offset.powi(2) - STAR_SPHERE_RADIUS.powi(2))
+        / (2.0 * parameters.star_sphere_offset))
+        .acos();
+    let tilt = (parameters.star_points as f64 * parameters.star_depth / 3.0).min(0.75 * cap_angle);
+    let turn = (3.0 * tilt).min(TAU / 4.0 - tilt - parameters.star_depth);
+    let star_spheres = (0..2 * parameters.star_points).map(|index| Sphere {
+        centre: Point3d {
             x: 0.0,
-            y: 0.0,
-            z: 1.0,
+            y: -parameters.star_sphere_offset
+                - starball_timeline(StarballTrack::StarSphereOffset).sample(animation_fraction)
+                    * parameters.star_sphere_travel,
+            z: 0.0,
         }
-    }
+        .rotate(
+            tilt + parameters.star_depth * (index % 2) as f64
+                + starball_timeline(StarballTrack::StarSphereZRotation).sample(animation_fraction)
+                    * turn,
+            &Point3d::z_axis(),
+        )
+        .expect("The z_axis vector is not zero.")
+        .rotate(
+            TAU * animation_fraction + TAU / (2 * parameters.star_points) as f64 * index as f64,
+            &Point3d::y_axis(),
+        )
+        .expect("The y_axis vector is not zero."),
+        radius: STAR_SPHERE_RADIUS,
+        grey_value: UNCOLOURED,
+        name: Some(format!("star sphere {index}")),
+        // The even spheres make the points of the star, and the odd ones fill the gaps between
+        // them with the colour of the ball
+        tags: tag_set(&["star", ["star-point", "star-gap"][index as usize % 2]]),
+    });
+
+    let mut spheres = vec![upper_sphere, lower_sphere, stripe_sphere];
+    spheres.extend(star_spheres);
+
+    Group::new(spheres)
 }
 
-impl Add for Point3d {
-    type Output = Self;
+#[derive(Clone, Copy, Debug)]
+enum StarballTrack {
+    LowerSphereOffset,
+    StripeSphereOffset,
+    StarSphereOffset,
+    StarSphereZRotation,
+}
 
-    fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-            z: self.z + rhs.z,
-        }
+// One row per keyframe: the fraction of the way through the animation, followed by the value of each
+// track in the order they are listed in `StarballTrack`
+const STARBALL_KEYFRAMES: [(f64, [f64; 4]); 7] = [
+    (0.0, [0.0, 0.0, 0.0, 0.0]),
+    (1.0 / 6.0, [0.0, 0.0, 0.0, 0.0]),
+    (2.0 / 6.0, [1.0, 0.0, 1.0, 0.0]),
+    (3.0 / 6.0, [1.0, 1.0, 1.0, 1.0]),
+    (4.0 / 6.0, [1.0, 1.0, 1.0, 1.0]),
+    (5.0 / 6.0, [1.0, 0.0, 1.0, 0.0]),
+    (1.0, [0.0, 0.0, 0.0, 0.0]),
+];
+
+impl StarballTrack {
+    const ALL: [Self; 4] = [
+        Self::LowerSphereOffset,
+        Self::StripeSphereOffset,
+        Self::StarSphereOffset,
+        Self::StarSphereZRotation,
+    ];
+
+    fn easing(self) -> Box<dyn Easing> {
+        Box::new(PositiveCos)
     }
 }
 
-impl Sub for Point3d {
-    type Output = Self;
+fn starball_timeline(track: StarballTrack) -> Timeline {
+    Timeline::from_table(&STARBALL_KEYFRAMES, track as usize).with_easing(track.easing())
+}
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        self + Self {
-            x: -rhs.x,
-            y: -rhs.y,
-            z: -rhs.z,
-        }
-    }
+// The name and caption of each phase, one for each step between rows of `STARBALL_KEYFRAMES`
+const STARBALL_PHASES: [(&str, &str); 6] = [
+    (
+        "Assembled",
+        "The starball: a white ball with a dark stripe and a star.",
+    ),
+    (
+        "Split",
+        "The lower white sphere moves down and the ten star spheres move up and out.",
+    ),
+    (
+        "Stripe and twist",
+        "The sphere that makes the stripe moves down, and the star spheres turn to show how their points alternate.",
+    ),
+    (
+        "Apart",
+        "Every part hangs separately.",
+    ),
+    (
+        "Untwist",
+        "The stripe sphere moves back up and the star spheres turn back.",
+    ),
+    (
+        "Reassemble",
+        "The lower sphere and the star spheres move back to make the starball again.",
+    ),
+];
+
+fn starball_phases() -> Vec<Phase> {
+    Phase::between_keyframes(&STARBALL_KEYFRAMES, &STARBALL_PHASES)
 }
 
-impl Mul<f64> for Point3d {
-    type Output = Self;
+// The star points are darker than the ball, and the spheres between them match it
+const STANDARD_SCHEME: &[(&str, u8)] = &[
+    ("ball", 210),
+    ("stripe", 50),
+    ("star-point", 100),
+    ("star-gap", 210),
+];
+
+/// The parameters that vary the starball, read from JSON and described in `PARAMETER_SCHEMA`.
+/// Every field is optional.
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Parameters {
+    star_points: u32,
+    star_depth: f64,
+    stripe_width: f64,
+    star_sphere_offset: f64,
+    star_sphere_travel: f64,
+    lower_sphere_offset: f64,
+    lower_sphere_travel: f64,
+    stripe_sphere_offset: f64,
+    stripe_sphere_travel: f64,
+}
 
-    fn mul(self, rhs: f64) -> Self::Output {
+impl Default for Parameters {
+    fn default() -> Self {
         Self {
-            x: self.x * rhs,
-            y: self.y * rhs,
-            z: self.z * rhs,
+            star_points: 5,
+            star_depth: TAU / 35.0,
+            stripe_width: 0.00012,
+            star_sphere_offset: 0.044,
+            star_sphere_travel: 3.4,
+            lower_sphere_offset: 0.0008,
+            lower_sphere_travel: 4.8,
+            stripe_sphere_offset: 0.0004,
+            stripe_sphere_travel: 2.4,
         }
     }
 }
 
-impl fmt::Display for Point3d {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}, {}, {}", self.x, self.y, self.z)
+const PARAMETER_SCHEMA: &[ParameterSchema] = &[
+    ParameterSchema {
+        name: "star_points",
+        description: "The number of points of the star. Each point and each gap between points is one sphere, spaced evenly around the ball.",
+        min: 2.0,
+        max: 12.0,
+    },
+    ParameterSchema {
+        name: "star_depth",
+        description: "How much further the spheres between the points are tilted away from the middle of the ball than the points themselves, in radians, which sets how deep the points are.",
+        min: 0.0,
+        max: TAU / 8.0,
+    },
+    ParameterSchema {
+        name: "stripe_width",
+        description: "How much larger the stripe sphere is than the ball, which sets how wide the stripe is.",
+        min: 0.0,
+        max: 0.001,
+    },
+    ParameterSchema {
+        name: "star_sphere_offset",
+        description: "How far the star spheres sit above the middle of the ball when it is assembled, which sets how large the star is. Below 0.041 the star spheres would not show above the ball.",
+        min: 0.041,
+        max: 0.1,
+    },
+    ParameterSchema {
+        name: "star_sphere_travel",
+        description: "How far the star spheres move up as the ball comes apart, in ball radii.",
+        min: 0.0,
+        max: 8.0,
+    },
+    ParameterSchema {
+        name: "lower_sphere_offset",
+        description: "How far the lower sphere sits below the upper sphere when the ball is assembled, so that each shows on its own half of the ball.",
+        min: 0.0001,
+        max: 0.01,
+    },
+    ParameterSchema {
+        name: "lower_sphere_travel",
+        description: "How far the lower sphere moves down as the ball comes apart, in ball radii.",
+        min: 0.0,
+        max: 8.0,
+    },
+    ParameterSchema {
+        name: "stripe_sphere_offset",
+        description: "How far the stripe sphere sits below the upper sphere when the ball is assembled, which with its width sets where the stripe is.",
+        min: 0.0,
+        max: 0.01,
+    },
+    ParameterSchema {
+        name: "stripe_sphere_travel",
+        description: "How far the stripe sphere moves down as the ball comes apart, in ball radii.",
+        min: 0.0,
+        max: 8.0,
+    },
+];
+
+impl AnimationParameters for Parameters {
+    const SCHEMA: &'static [ParameterSchema] = PARAMETER_SCHEMA;
+    // The animation tells apart spheres that look alike unless another scheme is chosen
+    const DEFAULT_SCHEME: SchemeName = SchemeName::Explanatory;
+
+    fn standard_scheme(&self) -> Scheme {
+        Scheme::new(STANDARD_SCHEME)
     }
-}
 
-struct Point2d {
-    x: f64,
-    y: f64,
+    // A white ball, and a different grey for every star sphere so that each can be followed as it
+    // turns. The points go from 100 and the gaps from 215, each getting 40 lighter from the first to
+    // the last, so that 5 points go up in steps of 10
+    fn explanatory_scheme(&self) -> Scheme {
+        let star_sphere_grey = |index: u32| {
+            (100 + 115 * (index % 2) + 40 * (index / 2) / (self.star_points - 1)) as u8
+        };
+        Scheme::new(&[("ball", 255)]).then(&Scheme {
+            rules: (0..2 * self.star_points)
+                .map(|index| (format!("star sphere {index}"), star_sphere_grey(index)))
+                .collect(),
+        })
+    }
 }
 
-impl Point2d {
-    fn distance(a: Self, b: Self) -> f64 {
-        ((a.x - b.x).powf(2.0) + (a.y - b.y).powf(2.0)).powf(0.5)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::animation_checks::AnimationChecks;
+    use common::easing::positive_cos;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn every_track_is_continuous() {
+        for track in continuity().tracks {
+            assert_eq!(
+                track.discontinuities,
+                vec![],
+                "{} is continuous.",
+                track.track
+            );
+        }
     }
-}
 
-struct Intersection {
-    sphere: Sphere,
-    distance: f64,
-}
+    #[test]
+    fn end_of_the_animation_leads_into_the_first_frame() {
+        assert_eq!(continuity().unmatched_at_loop, Vec::<String>::new());
+    }
 
-fn sphere_with_intersection_distance(
-    x: f64,
-    y: f64,
-) -> impl FnMut(&Sphere) -> Option<Intersection> {
-    move |sphere| {
-        let distance = Point2d::distance(
-            Point2d { x, y },
-            Point2d {
-                x: sphere.centre.x as f64,
-                y: sphere.centre.y as f64,
-            },
-        );
-        let radius = sphere.radius as f64;
-        if distance < radius {
-            Some(Intersection {
-                sphere: sphere.clone(),
-                distance: 1000.0 + sphere.centre.z as f64
-                    - (radius.powf(2.0) - distance.powf(2.0)).powf(0.5)
-                    - 1000.0,
-            })
-        } else {
-            None
+    // The piecewise functions the keyframes replaced, each giving a track's value at fraction `a` of
+    // the animation
+    fn starball_lower_sphere_offset(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => starball_lower_sphere_offset(a % 1.0 + 1.0),
+            x if x < 1.0 / 6.0 => 0.0,
+            x if x < 2.0 / 6.0 => positive_cos(a * TAU * 3.0),
+            x if x < 5.0 / 6.0 => 1.0,
+            x if x < 1.0 => 1.0 - positive_cos(a * TAU * 3.0),
+            _ => starball_lower_sphere_offset(a % 1.0),
         }
     }
-}
 
-fn starball_lower_sphere_offset(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => starball_lower_sphere_offset(a % 1.0 + 1.0),
-        x if x < 1.0 / 6.0 => 0.0,
-        x if x < 2.0 / 6.0 => positive_cos(a * TAU * 3.0),
-        x if x < 5.0 / 6.0 => 1.0,
-        x if x < 1.0 => 1.0 - positive_cos(a * TAU * 3.0),
-        _ => starball_lower_sphere_offset(a % 1.0),
+    fn starball_stripe_sphere_offset(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => starball_stripe_sphere_offset(a % 1.0 + 1.0),
+            x if x < 2.0 / 6.0 => 0.0,
+            x if x < 3.0 / 6.0 => 1.0 - positive_cos(a * TAU * 3.0),
+            x if x < 4.0 / 6.0 => 1.0,
+            x if x < 5.0 / 6.0 => positive_cos(a * TAU * 3.0),
+            x if x < 1.0 => 0.0,
+            _ => starball_stripe_sphere_offset(a % 1.0),
+        }
     }
-}
 
-fn starball_stripe_sphere_offset(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => starball_stripe_sphere_offset(a % 1.0 + 1.0),
-        x if x < 2.0 / 6.0 => 0.0,
-        x if x < 3.0 / 6.0 => 1.0 - positive_cos(a * TAU * 3.0),
-        x if x < 4.0 / 6.0 => 1.0,
-        x if x < 5.0 / 6.0 => positive_cos(a * TAU * 3.0),
-        x if x < 1.0 => 0.0,
-        _ => starball_stripe_sphere_offset(a % 1.0),
+    fn starball_star_sphere_offset(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => starball_star_sphere_offset(a % 1.0 + 1.0),
+            x if x < 1.0 / 6.0 => 0.0,
+            x if x < 2.0 / 6.0 => positive_cos(a * TAU * 3.0),
+            x if x < 5.0 / 6.0 => 1.0,
+            x if x < 1.0 => 1.0 - positive_cos(a * TAU * 3.0),
+            _ => starball_star_sphere_offset(a % 1.0),
+        }
     }
-}
 
-fn starball_star_sphere_offset(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => starball_star_sphere_offset(a % 1.0 + 1.0),
-        x if x < 1.0 / 6.0 => 0.0,
-        x if x < 2.0 / 6.0 => positive_cos(a * TAU * 3.0),
-        x if x < 5.0 / 6.0 => 1.0,
-        x if x < 1.0 => 1.0 - positive_cos(a * TAU * 3.0),
-        _ => starball_star_sphere_offset(a % 1.0),
+    fn starball_star_sphere_z_rotation(a: f64) -> f64 {
+        match a {
+            x if x < 0.0 => starball_star_sphere_z_rotation(a % 1.0 + 1.0),
+            x if x < 2.0 / 6.0 => 0.0,
+            x if x < 3.0 / 6.0 => 1.0 - positive_cos(a * TAU * 3.0),
+            x if x < 4.0 / 6.0 => 1.0,
+            x if x < 5.0 / 6.0 => positive_cos(a * TAU * 3.0),
+            x if x < 1.0 => 0.0,
+            _ => starball_star_sphere_z_rotation(a % 1.0),
+        }
     }
-}
 
-fn starball_star_sphere_z_rotation(a: f64) -> f64 {
-    match a {
-        x if x < 0.0 => starball_star_sphere_z_rotation(a % 1.0 + 1.0),
-        x if x < 2.0 / 6.0 => 0.0,
-        x if x < 3.0 / 6.0 => 1.0 - positive_cos(a * TAU * 3.0),
-        x if x < 4.0 / 6.0 => 1.0,
-        x if x < 5.0 / 6.0 => positive_cos(a * TAU * 3.0),
-        x if x < 1.0 => 0.0,
-        _ => starball_star_sphere_z_rotation(a % 1.0),
+    #[test]
+    fn the_keyframes_match_the_original_piecewise_functions() {
+        for step in -22..=242 {
+            let a = step as f64 / 220.0;
+            for (track, original) in [
+                (
+                    StarballTrack::LowerSphereOffset,
+                    starball_lower_sphere_offset as fn(f64) -> f64,
+                ),
+                (
+                    StarballTrack::StripeSphereOffset,
+                    starball_stripe_sphere_offset,
+                ),
+                (StarballTrack::StarSphereOffset, starball_star_sphere_offset),
+                (
+                    StarballTrack::StarSphereZRotation,
+                    starball_star_sphere_z_rotation,
+                ),
+            ] {
+                let sampled = starball_timeline(track).sample(a);
+                assert!(
+                    (sampled - original(a)).abs() < 1e-9,
+                    "{track:?} at {a} is {sampled} rather than {}.",
+                    original(a)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn a_seven_point_star_has_a_sphere_for_every_point_and_gap() {
+        let options =
+            FrameOptions::<Parameters>::from_json(r#"{"parameters": {"star_points": 7}}"#)
+                .expect("The options are valid.");
+        let group = coloured_frame(
+            0.5,
+            &options.parameters,
+            &options.colour_scheme.scheme(&options.parameters),
+        );
+        let star_greys: BTreeSet<u8> = group.spheres[3..]
+            .iter()
+            .map(|sphere| sphere.grey_value)
+            .collect();
+        assert_eq!(group.spheres.len(), 3 + 14);
+        assert_eq!(star_greys.len(), 14);
+        assert!(
+            FrameOptions::<Parameters>::from_json(r#"{"parameters": {"star_depth": -1.0}}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn the_star_tilt_and_turn_follow_the_points_and_depth() {
+        // The angle of each star sphere's centre from the top of the ball, which rotating about the
+        // vertical axis leaves unchanged
+        let tilts = |animation_fraction: f64, parameters: &Parameters| -> Vec<f64> {
+            starball(animation_fraction, parameters).spheres[3..]
+                .iter()
+                .map(|sphere| {
+                    (-sphere.centre.y / Point3d::distance(&sphere.centre, &Point3d::origin()))
+                        .acos()
+                })
+                .collect()
+        };
+        let default = Parameters::default();
+        assert!((tilts(0.0, &default)[0] - TAU / 21.0).abs() < 1e-9);
+        assert!((tilts(0.5, &default)[0] - TAU / 21.0 - TAU / 7.0).abs() < 1e-9);
+
+        let parameters = Parameters {
+            star_points: 7,
+            star_depth: TAU / 50.0,
+            ..Parameters::default()
+        };
+        let tilt = 7.0 * TAU / 50.0 / 3.0;
+        for (animation_fraction, turn) in [(0.0, 0.0), (0.5, 3.0 * tilt)] {
+            let tilts = tilts(animation_fraction, &parameters);
+            assert_eq!(tilts.len(), 14);
+            for (index, actual) in tilts.into_iter().enumerate() {
+                let expected = tilt + turn + TAU / 50.0 * (index % 2) as f64;
+                assert!(
+                    (actual - expected).abs() < 1e-9,
+                    "Star sphere {index} at {animation_fraction} is tilted by {actual} rather than {expected}."
+                );
+            }
+        }
     }
-}
 
-fn positive_cos(a: f64) -> f64 {
-    (a.cos() + 1.0) * 0.5
+    #[test]
+    fn the_largest_star_covers_the_top_of_the_ball_and_never_turns_past_its_side() {
+        let parameters = Parameters {
+            star_points: 12,
+            star_depth: TAU / 8.0,
+            ..Parameters::default()
+        };
+        assert_eq!(parameters::validate(&parameters), Ok(()));
+        let star_spheres =
+            |animation_fraction| starball(animation_fraction, &parameters).spheres[3..].to_vec();
+        let top = Point3d {
+            x: 0.0,
+            y: -1.0,
+            z: 0.0,
+        };
+        assert!(star_spheres(0.0)
+            .iter()
+            .step_by(2)
+            .all(|sphere| Point3d::distance(&sphere.centre, &top) < sphere.radius));
+        for frame_index in 0..=100 {
+            for sphere in star_spheres(frame_index as f64 / 100.0) {
+                assert!(
+                    sphere.centre.y < 1e-9,
+                    "{} stays above the side of the ball.",
+                    sphere.label(0)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn the_checks_shared_by_every_animation_pass() {
+        AnimationChecks {
+            name: NAME,
+            duration_seconds: DURATION_SECONDS,
+            scene_description: SCENE_DESCRIPTION,
+            #[cfg(feature = "script")]
+            script: SCRIPT,
+            coloured_frame,
+            render_frame,
+        }
+        .run();
+    }
 }